@@ -0,0 +1,46 @@
+use crate::models::JSONResponse;
+use actix_web::{web, HttpResponse};
+use sqlx::SqlitePool;
+use synth_common::models::Calendar;
+use synth_common::queries;
+
+/// Return a list of all calendars, along with their holidays
+pub async fn list(db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    match queries::select_calendars(&db_pool).await {
+        Ok(calendars) => {
+            let response_data = JSONResponse::<Calendar> {
+                data: Some(calendars),
+                errors: None,
+            };
+            HttpResponse::Ok().json(response_data)
+        }
+        Err(_) => {
+            let response_data = JSONResponse::<Calendar> {
+                data: None,
+                errors: Some(vec!["Failed to get calendars!".to_string()]),
+            };
+            HttpResponse::InternalServerError().json(response_data)
+        }
+    }
+}
+
+/// Get a specific calendar
+pub async fn get(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    let id = path.to_string();
+    match queries::select_calendar_by_id(&id, &db_pool).await {
+        Ok(calendar) => {
+            let response_data = JSONResponse::<Calendar> {
+                data: Some(vec![calendar]),
+                errors: None,
+            };
+            HttpResponse::Ok().json(response_data)
+        }
+        Err(_) => {
+            let response_data = JSONResponse::<Calendar> {
+                data: None,
+                errors: Some(vec![format!("Calendar '{}' not found!", id)]),
+            };
+            HttpResponse::NotFound().json(response_data)
+        }
+    }
+}
@@ -1,4 +1,8 @@
+pub mod calendars;
+pub mod pipeline_runs;
 pub mod pipelines;
+pub mod pools;
 pub mod task_instances;
 pub mod tasks;
 pub mod utility;
+pub mod workers;
@@ -0,0 +1,79 @@
+use crate::models::JSONResponse;
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use sqlx::SqlitePool;
+use synth_common::models::PipelineRun;
+use synth_common::{queries, schedule};
+
+/// Get a PipelineRun by ID
+pub async fn get(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    let id = path.to_string();
+    let result = queries::select_pipeline_run_by_id(&id, &db_pool).await;
+
+    match result {
+        Ok(pipeline_run) => {
+            let response_data = JSONResponse::<PipelineRun> {
+                data: Some(vec![pipeline_run]),
+                errors: None,
+            };
+            HttpResponse::Ok().json(response_data)
+        }
+        Err(sqlx::Error::RowNotFound) => {
+            let response_data = JSONResponse::<PipelineRun> {
+                data: None,
+                errors: Some(vec![format!("Pipeline run '{}' not found!", id)]),
+            };
+            HttpResponse::NotFound().json(response_data)
+        }
+        Err(_) => {
+            let response_data = JSONResponse::<PipelineRun> {
+                data: None,
+                errors: Some(vec!["Failed to get pipeline run!".to_string()]),
+            };
+            HttpResponse::InternalServerError().json(response_data)
+        }
+    }
+}
+
+/// Cancel a queued or running PipelineRun along with its unfinished TaskInstances
+pub async fn cancel(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    let id = path.to_string();
+    let Ok(pipeline_run) = queries::select_pipeline_run_by_id(&id, &db_pool).await else {
+        let response_data = JSONResponse::<PipelineRun> {
+            data: None,
+            errors: Some(vec![format!("Pipeline run '{}' not found!", id)]),
+        };
+        return HttpResponse::NotFound().json(response_data);
+    };
+
+    let execution_end = schedule::format_timestamp(&Utc::now());
+    match queries::cancel_pipeline_run(&id, &execution_end, &db_pool).await {
+        Ok(true) => {
+            let pipeline_run = queries::select_pipeline_run_by_id(&id, &db_pool)
+                .await
+                .unwrap();
+            let response_data = JSONResponse::<PipelineRun> {
+                data: Some(vec![pipeline_run]),
+                errors: None,
+            };
+            HttpResponse::Ok().json(response_data)
+        }
+        Ok(false) => {
+            let response_data = JSONResponse::<PipelineRun> {
+                data: None,
+                errors: Some(vec![format!(
+                    "Pipeline run '{}' is already {}!",
+                    id, pipeline_run.status
+                )]),
+            };
+            HttpResponse::Conflict().json(response_data)
+        }
+        Err(_) => {
+            let response_data = JSONResponse::<PipelineRun> {
+                data: None,
+                errors: Some(vec!["Failed to cancel the pipeline run!".to_string()]),
+            };
+            HttpResponse::InternalServerError().json(response_data)
+        }
+    }
+}
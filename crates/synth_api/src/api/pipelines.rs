@@ -1,17 +1,23 @@
 use crate::models::JSONResponse;
 use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use sqlx::types::Json;
 use sqlx::SqlitePool;
-use synth_common::models::Pipeline;
-use synth_common::queries;
+use synth_common::models::{
+    Backfill, Pipeline, PipelineRun, PipelineRunStatus, RunTrigger, Trigger,
+};
+use synth_common::schedule::Schedule;
+use synth_common::{queries, schedule};
+use uuid::Uuid;
 
 /// Return a list of all pipelines
 pub async fn list(db_pool: web::Data<SqlitePool>) -> HttpResponse {
     let result = queries::select_pipelines(&db_pool).await;
 
     match result {
-        Ok(_) => {
+        Ok(pipelines) => {
             let response_data = JSONResponse::<Pipeline> {
-                data: None,
+                data: Some(pipelines),
                 errors: None,
             };
             HttpResponse::Ok().json(response_data)
@@ -52,6 +58,23 @@ pub async fn get(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> Htt
 /// Create a Pipeline
 pub async fn create(pipeline: web::Json<Pipeline>, db_pool: web::Data<SqlitePool>) -> HttpResponse {
     let pipeline = pipeline.into_inner();
+    if pipeline.max_active_runs.is_some_and(|max| max < 1) {
+        let response_data = JSONResponse::<Pipeline> {
+            data: None,
+            errors: Some(vec!["max_active_runs must be at least 1!".to_string()]),
+        };
+        return HttpResponse::BadRequest().json(response_data);
+    }
+    let calendar = queries::select_pipeline_calendar(&pipeline, &db_pool)
+        .await
+        .unwrap();
+    if let Err(e) = Schedule::for_pipeline(&pipeline, calendar.as_ref()) {
+        let response_data = JSONResponse::<Pipeline> {
+            data: None,
+            errors: Some(vec![e]),
+        };
+        return HttpResponse::BadRequest().json(response_data);
+    }
     let result = queries::upsert_pipeline(&pipeline, &db_pool).await;
 
     match result {
@@ -71,3 +94,212 @@ pub async fn create(pipeline: web::Json<Pipeline>, db_pool: web::Data<SqlitePool
         }
     }
 }
+
+/// Stop the scheduler from scheduling or starting runs of a Pipeline
+pub async fn pause(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    set_paused(&path, true, &db_pool).await
+}
+
+/// Let the scheduler pick a paused Pipeline back up
+pub async fn resume(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    set_paused(&path, false, &db_pool).await
+}
+
+/// Pause or resume a Pipeline, responding with the updated Pipeline
+async fn set_paused(id: &str, is_paused: bool, db_pool: &SqlitePool) -> HttpResponse {
+    let result = queries::set_pipeline_paused(id, is_paused, db_pool).await;
+    match result {
+        Ok(true) => {
+            let pipeline = queries::select_pipeline_by_id(id, db_pool).await.unwrap();
+            let response_data = JSONResponse::<Pipeline> {
+                data: Some(vec![pipeline]),
+                errors: None,
+            };
+            HttpResponse::Ok().json(response_data)
+        }
+        Ok(false) => {
+            let response_data = JSONResponse::<Pipeline> {
+                data: None,
+                errors: Some(vec![format!("Pipeline '{}' not found!", id)]),
+            };
+            HttpResponse::NotFound().json(response_data)
+        }
+        Err(_) => {
+            let response_data = JSONResponse::<Pipeline> {
+                data: None,
+                errors: Some(vec!["Failed to update the pipeline!".to_string()]),
+            };
+            HttpResponse::InternalServerError().json(response_data)
+        }
+    }
+}
+
+/// Return the runs of a pipeline, most recent first
+pub async fn runs(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    let id = path.to_string();
+    if queries::select_pipeline_by_id(&id, &db_pool).await.is_err() {
+        let response_data = JSONResponse::<PipelineRun> {
+            data: None,
+            errors: Some(vec![format!("Pipeline '{}' not found!", id)]),
+        };
+        return HttpResponse::NotFound().json(response_data);
+    }
+
+    match queries::select_pipeline_runs_by_pipeline_id(&id, &db_pool).await {
+        Ok(pipeline_runs) => {
+            let response_data = JSONResponse::<PipelineRun> {
+                data: Some(pipeline_runs),
+                errors: None,
+            };
+            HttpResponse::Ok().json(response_data)
+        }
+        Err(_) => {
+            let response_data = JSONResponse::<PipelineRun> {
+                data: None,
+                errors: Some(vec!["Failed to get pipeline runs!".to_string()]),
+            };
+            HttpResponse::InternalServerError().json(response_data)
+        }
+    }
+}
+
+/// Queue a PipelineRun for every schedule tick within a range of time
+pub async fn backfill(
+    path: web::Path<String>,
+    backfill: web::Json<Backfill>,
+    db_pool: web::Data<SqlitePool>,
+) -> HttpResponse {
+    let id = path.to_string();
+    let pipeline = match queries::select_pipeline_by_id(&id, &db_pool).await {
+        Ok(pipeline) => pipeline,
+        Err(_) => {
+            let response_data = JSONResponse::<PipelineRun> {
+                data: None,
+                errors: Some(vec![format!("Pipeline '{}' not found!", id)]),
+            };
+            return HttpResponse::NotFound().json(response_data);
+        }
+    };
+
+    let calendar = queries::select_pipeline_calendar(&pipeline, &db_pool)
+        .await
+        .unwrap();
+    let fire_times = schedule::parse_timestamp(&backfill.start)
+        .and_then(|start| Ok((start, schedule::parse_timestamp(&backfill.end)?)))
+        .and_then(|(start, end)| {
+            Schedule::for_pipeline(&pipeline, calendar.as_ref())?.fire_times_between(&start, &end)
+        });
+    let fire_times = match fire_times {
+        Ok(fire_times) => fire_times,
+        Err(e) => {
+            let response_data = JSONResponse::<PipelineRun> {
+                data: None,
+                errors: Some(vec![e]),
+            };
+            return HttpResponse::BadRequest().json(response_data);
+        }
+    };
+
+    // Ticks that already have a run are skipped, so backfills can be repeated
+    let mut pipeline_runs = Vec::new();
+    for fire_time in fire_times {
+        let pipeline_run = PipelineRun {
+            id: Uuid::new_v4().to_string(),
+            pipeline_id: pipeline.id.clone(),
+            scheduled_time: schedule::format_timestamp(&fire_time),
+            trigger: RunTrigger::Backfill,
+            status: PipelineRunStatus::Queued,
+            created_at: schedule::format_timestamp(&Utc::now()),
+            ..Default::default()
+        };
+        match queries::insert_pipeline_run(&pipeline_run, &db_pool).await {
+            Ok(true) => pipeline_runs.push(pipeline_run),
+            Ok(false) => continue,
+            Err(_) => {
+                let response_data = JSONResponse::<PipelineRun> {
+                    data: None,
+                    errors: Some(vec!["Failed to queue the backfill!".to_string()]),
+                };
+                return HttpResponse::InternalServerError().json(response_data);
+            }
+        }
+    }
+
+    let response_data = JSONResponse::<PipelineRun> {
+        data: Some(pipeline_runs),
+        errors: None,
+    };
+    HttpResponse::Created().json(response_data)
+}
+
+/// Queue a manual PipelineRun, for the current time unless a logical date is given
+pub async fn trigger(
+    path: web::Path<String>,
+    trigger: web::Json<Trigger>,
+    db_pool: web::Data<SqlitePool>,
+) -> HttpResponse {
+    let id = path.to_string();
+    let trigger = trigger.into_inner();
+    if queries::select_pipeline_by_id(&id, &db_pool).await.is_err() {
+        let response_data = JSONResponse::<PipelineRun> {
+            data: None,
+            errors: Some(vec![format!("Pipeline '{}' not found!", id)]),
+        };
+        return HttpResponse::NotFound().json(response_data);
+    }
+
+    let scheduled_time = match trigger.logical_date.as_deref() {
+        Some(logical_date) => schedule::parse_timestamp(logical_date),
+        None => Ok(Utc::now()),
+    };
+    let scheduled_time = match scheduled_time.and_then(|time| {
+        trigger.validate_params()?;
+        Ok(time)
+    }) {
+        Ok(scheduled_time) => scheduled_time,
+        Err(e) => {
+            let response_data = JSONResponse::<PipelineRun> {
+                data: None,
+                errors: Some(vec![e]),
+            };
+            return HttpResponse::BadRequest().json(response_data);
+        }
+    };
+
+    let pipeline_run = PipelineRun {
+        id: Uuid::new_v4().to_string(),
+        pipeline_id: id.clone(),
+        scheduled_time: schedule::format_timestamp(&scheduled_time),
+        trigger: RunTrigger::Manual,
+        status: PipelineRunStatus::Queued,
+        created_at: schedule::format_timestamp(&Utc::now()),
+        params: Json(trigger.params),
+        ..Default::default()
+    };
+    match queries::insert_pipeline_run(&pipeline_run, &db_pool).await {
+        Ok(true) => {
+            let response_data = JSONResponse::<PipelineRun> {
+                data: Some(vec![pipeline_run]),
+                errors: None,
+            };
+            HttpResponse::Created().json(response_data)
+        }
+        Ok(false) => {
+            let response_data = JSONResponse::<PipelineRun> {
+                data: None,
+                errors: Some(vec![format!(
+                    "Pipeline '{}' already has a run for {}!",
+                    id, pipeline_run.scheduled_time
+                )]),
+            };
+            HttpResponse::Conflict().json(response_data)
+        }
+        Err(_) => {
+            let response_data = JSONResponse::<PipelineRun> {
+                data: None,
+                errors: Some(vec!["Failed to trigger the pipeline!".to_string()]),
+            };
+            HttpResponse::InternalServerError().json(response_data)
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use crate::models::JSONResponse;
+use actix_web::{web, HttpResponse};
+use sqlx::SqlitePool;
+use synth_common::models::ResourcePool;
+use synth_common::queries;
+
+/// Return a list of all pools, along with how many of their slots are in use
+pub async fn list(db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    match queries::select_pools(&db_pool).await {
+        Ok(pools) => {
+            let response_data = JSONResponse::<ResourcePool> {
+                data: Some(pools),
+                errors: None,
+            };
+            HttpResponse::Ok().json(response_data)
+        }
+        Err(_) => {
+            let response_data = JSONResponse::<ResourcePool> {
+                data: None,
+                errors: Some(vec!["Failed to get pools!".to_string()]),
+            };
+            HttpResponse::InternalServerError().json(response_data)
+        }
+    }
+}
+
+/// Get a specific pool
+pub async fn get(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    let id = path.to_string();
+    match queries::select_pool_by_id(&id, &db_pool).await {
+        Ok(pool) => {
+            let response_data = JSONResponse::<ResourcePool> {
+                data: Some(vec![pool]),
+                errors: None,
+            };
+            HttpResponse::Ok().json(response_data)
+        }
+        Err(_) => {
+            let response_data = JSONResponse::<ResourcePool> {
+                data: None,
+                errors: Some(vec![format!("Pool '{}' not found!", id)]),
+            };
+            HttpResponse::NotFound().json(response_data)
+        }
+    }
+}
+
+/// Create a pool, or change the number of slots of an existing one
+pub async fn create(pool: web::Json<ResourcePool>, db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    let pool = pool.into_inner();
+    if pool.slots < 1 {
+        let response_data = JSONResponse::<ResourcePool> {
+            data: None,
+            errors: Some(vec!["A pool needs at least 1 slot!".to_string()]),
+        };
+        return HttpResponse::BadRequest().json(response_data);
+    }
+    // Shrinking a pool mustn't strand the Tasks that need more slots, nor
+    // leave its running Tasks taking up more slots than it has
+    match queries::upsert_pool(&pool, &db_pool).await {
+        Ok(true) => {
+            let pool = queries::select_pool_by_id(&pool.id, &db_pool)
+                .await
+                .unwrap();
+            let response_data = JSONResponse::<ResourcePool> {
+                data: Some(vec![pool]),
+                errors: None,
+            };
+            HttpResponse::Created().json(response_data)
+        }
+        Ok(false) => {
+            let response_data = JSONResponse::<ResourcePool> {
+                data: None,
+                errors: Some(vec![format!(
+                    "Pool '{}' has Tasks that need more than {} slot(s)!",
+                    pool.id, pool.slots
+                )]),
+            };
+            HttpResponse::Conflict().json(response_data)
+        }
+        Err(_) => {
+            let response_data = JSONResponse::<ResourcePool> {
+                data: None,
+                errors: Some(vec!["Failed to create the pool!".to_string()]),
+            };
+            HttpResponse::InternalServerError().json(response_data)
+        }
+    }
+}
@@ -1,11 +1,35 @@
-use crate::models::JSONResponse;
-use actix_web::{web, HttpResponse};
+use crate::models::{JSONResponse, TaskInstanceFilter};
+use actix_web::web::Bytes;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
 use sqlx::SqlitePool;
-use synth_common::models::TaskInstance;
+use synth_common::models::{Clear, TaskInstance, TaskLog, TaskStatus};
+use synth_common::queries;
+use synth_common::{dag, schedule};
+use tokio::sync::mpsc::{self, Sender};
+use tokio::time::{self, Duration};
+use tokio_stream::wrappers::ReceiverStream;
+use uuid::Uuid;
 
-pub async fn list(db_pool: web::Data<SqlitePool>) -> HttpResponse {
-    let task_instances = sqlx::query_as!(TaskInstance, "SELECT * FROM task_instances")
-        .fetch_all(db_pool.get_ref())
+/// Seconds between checks for new log lines while streaming
+const LOG_POLL_INTERVAL: u64 = 1;
+
+pub async fn list(
+    filter: web::Query<TaskInstanceFilter>,
+    db_pool: web::Data<SqlitePool>,
+) -> HttpResponse {
+    let status = match filter.status.as_deref().map(str::parse::<TaskStatus>) {
+        Some(Ok(status)) => Some(status),
+        Some(Err(e)) => {
+            let response_data = JSONResponse::<TaskInstance> {
+                data: None,
+                errors: Some(vec![e]),
+            };
+            return HttpResponse::BadRequest().json(response_data);
+        }
+        None => None,
+    };
+    let task_instances = queries::select_task_instances(status, &db_pool)
         .await
         .unwrap();
 
@@ -18,14 +42,9 @@ pub async fn list(db_pool: web::Data<SqlitePool>) -> HttpResponse {
 
 pub async fn get(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> HttpResponse {
     let id = path.to_string();
-    let task_instance = sqlx::query_as!(
-        TaskInstance,
-        "SELECT * FROM task_instances WHERE id = ?",
-        id
-    )
-    .fetch_one(db_pool.get_ref())
-    .await
-    .unwrap();
+    let task_instance = queries::select_task_instance_by_id(&id, &db_pool)
+        .await
+        .unwrap();
 
     let response_data = JSONResponse::<TaskInstance> {
         data: Some(vec![task_instance]),
@@ -33,3 +52,266 @@ pub async fn get(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> Htt
     };
     HttpResponse::Ok().json(response_data)
 }
+
+/// Cancel a queued or running TaskInstance, stopping its process
+pub async fn cancel(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    let id = path.to_string();
+    let Ok(task_instance) = queries::select_task_instance_by_id(&id, &db_pool).await else {
+        let response_data = JSONResponse::<TaskInstance> {
+            data: None,
+            errors: Some(vec![format!("Task instance '{}' not found!", id)]),
+        };
+        return HttpResponse::NotFound().json(response_data);
+    };
+
+    let execution_end = schedule::format_timestamp(&Utc::now());
+    let cancelled =
+        queries::cancel_task_instance(&id, task_instance.status, &execution_end, &db_pool).await;
+    match cancelled {
+        Ok(true) => {
+            let task_instance = queries::select_task_instance_by_id(&id, &db_pool)
+                .await
+                .unwrap();
+            let response_data = JSONResponse::<TaskInstance> {
+                data: Some(vec![task_instance]),
+                errors: None,
+            };
+            HttpResponse::Ok().json(response_data)
+        }
+        Ok(false) => {
+            let response_data = JSONResponse::<TaskInstance> {
+                data: None,
+                errors: Some(vec![format!(
+                    "Task instance '{}' is already {}!",
+                    id, task_instance.status
+                )]),
+            };
+            HttpResponse::Conflict().json(response_data)
+        }
+        Err(_) => {
+            let response_data = JSONResponse::<TaskInstance> {
+                data: None,
+                errors: Some(vec!["Failed to cancel the task instance!".to_string()]),
+            };
+            HttpResponse::InternalServerError().json(response_data)
+        }
+    }
+}
+
+/// Clear a finished TaskInstance so its Task runs again within the same
+/// PipelineRun, optionally together with all of its downstream Tasks
+pub async fn clear(
+    path: web::Path<String>,
+    clear: web::Json<Clear>,
+    db_pool: web::Data<SqlitePool>,
+) -> HttpResponse {
+    let id = path.to_string();
+    let Ok(task_instance) = queries::select_task_instance_by_id(&id, &db_pool).await else {
+        let response_data = JSONResponse::<TaskInstance> {
+            data: None,
+            errors: Some(vec![format!("Task instance '{}' not found!", id)]),
+        };
+        return HttpResponse::NotFound().json(response_data);
+    };
+    if task_instance.pipeline_run_id.is_none() {
+        let response_data = JSONResponse::<TaskInstance> {
+            data: None,
+            errors: Some(vec![format!(
+                "Task instance '{}' doesn't belong to a pipeline run!",
+                id
+            )]),
+        };
+        return HttpResponse::BadRequest().json(response_data);
+    }
+
+    match clear_task_instance(&task_instance, clear.downstream, &db_pool).await {
+        Ok(Some(task_instances)) => {
+            let response_data = JSONResponse::<TaskInstance> {
+                data: Some(task_instances),
+                errors: None,
+            };
+            HttpResponse::Ok().json(response_data)
+        }
+        Ok(None) => {
+            let response_data = JSONResponse::<TaskInstance> {
+                data: None,
+                errors: Some(vec![format!(
+                    "Pipeline run of task instance '{}' is still active!",
+                    id
+                )]),
+            };
+            HttpResponse::Conflict().json(response_data)
+        }
+        Err(_) => {
+            let response_data = JSONResponse::<TaskInstance> {
+                data: None,
+                errors: Some(vec!["Failed to clear the task instance!".to_string()]),
+            };
+            HttpResponse::InternalServerError().json(response_data)
+        }
+    }
+}
+
+/// Add a new attempt of the Task of a TaskInstance, and of its downstream
+/// Tasks if requested, keeping the scheduled time of the PipelineRun. The new
+/// attempts are pending until the scheduler queues them in DAG order.
+///
+/// Returns the pending TaskInstances, or `None` if the PipelineRun hasn't
+/// finished yet.
+pub async fn clear_task_instance(
+    task_instance: &TaskInstance,
+    downstream: bool,
+    db_pool: &SqlitePool,
+) -> Result<Option<Vec<TaskInstance>>, sqlx::Error> {
+    let Some(pipeline_run_id) = task_instance.pipeline_run_id.as_deref() else {
+        return Ok(None);
+    };
+    let tasks = queries::select_task_by_pipeline_id(&task_instance.pipeline_id, db_pool).await?;
+    let mut task_ids = vec![task_instance.task_id.clone()];
+    if downstream {
+        task_ids.extend(dag::find_downstream_tasks(&tasks, &task_instance.task_id));
+    }
+
+    let priority_weights = dag::priority_weights(&tasks);
+    let run_task_instances =
+        queries::select_task_instances_by_pipeline_run_id(pipeline_run_id, db_pool).await?;
+    let task_instances: Vec<TaskInstance> = task_ids
+        .into_iter()
+        .map(|task_id| {
+            let retries = tasks
+                .iter()
+                .find(|task| task.id == task_id)
+                .map_or(0, |task| i64::from(task.retries));
+            let attempt = run_task_instances
+                .iter()
+                .filter(|other| other.task_id == task_id)
+                .map(|other| other.attempt)
+                .max()
+                .unwrap_or(0)
+                + 1;
+            let priority_weight = priority_weights.get(&task_id).copied().unwrap_or(1);
+            TaskInstance {
+                id: Uuid::new_v4().to_string(),
+                task_id,
+                pipeline_id: task_instance.pipeline_id.clone(),
+                pipeline_run_id: Some(pipeline_run_id.to_string()),
+                scheduled_time: task_instance.scheduled_time.clone(),
+                status: TaskStatus::Pending,
+                created_at: schedule::format_timestamp(&Utc::now()),
+                attempt,
+                last_attempt: attempt + retries,
+                priority_weight,
+                ..Default::default()
+            }
+        })
+        .collect();
+
+    let cleared = queries::clear_task_instances(pipeline_run_id, &task_instances, db_pool).await?;
+    Ok(cleared.then_some(task_instances))
+}
+
+/// Get the interleaved stdout and stderr lines of a TaskInstance
+pub async fn logs(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    let id = path.to_string();
+    let result = queries::select_task_logs_by_task_instance_id(&id, &db_pool).await;
+
+    match result {
+        Ok(task_logs) => {
+            let response_data = JSONResponse::<TaskLog> {
+                data: Some(task_logs),
+                errors: None,
+            };
+            HttpResponse::Ok().json(response_data)
+        }
+        Err(_) => {
+            let response_data = JSONResponse::<TaskLog> {
+                data: None,
+                errors: Some(vec!["Failed to get the task logs!".to_string()]),
+            };
+            HttpResponse::InternalServerError().json(response_data)
+        }
+    }
+}
+
+/// Stream the log lines of a TaskInstance as Server-Sent Events.
+///
+/// Every line is sent as a `log` event with the line's ID as the event ID,
+/// so clients can resume with the `Last-Event-ID` header. Once the TaskInstance
+/// has finished and all of its lines were sent, an `end` event carrying the
+/// final status closes the stream.
+pub async fn stream_logs(
+    request: HttpRequest,
+    path: web::Path<String>,
+    db_pool: web::Data<SqlitePool>,
+) -> HttpResponse {
+    let id = path.to_string();
+    if queries::select_task_instance_by_id(&id, &db_pool)
+        .await
+        .is_err()
+    {
+        let response_data = JSONResponse::<TaskLog> {
+            data: None,
+            errors: Some(vec![format!("Task instance '{}' not found!", id)]),
+        };
+        return HttpResponse::NotFound().json(response_data);
+    }
+    let last_event_id = request
+        .headers()
+        .get("Last-Event-ID")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<i64>().ok())
+        .unwrap_or(0);
+
+    let (sender, receiver) = mpsc::channel(16);
+    let db_pool = db_pool.get_ref().clone();
+    actix_web::rt::spawn(follow_logs(id, last_event_id, sender, db_pool));
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(ReceiverStream::new(receiver))
+}
+
+/// Poll for new log lines and send them as events until the TaskInstance finishes
+async fn follow_logs(
+    task_instance_id: String,
+    mut last_id: i64,
+    sender: Sender<Result<Bytes, actix_web::Error>>,
+    db_pool: SqlitePool,
+) {
+    loop {
+        // Check the status before reading, so no trailing lines are missed
+        let finished_status = match queries::select_task_instance_by_id(&task_instance_id, &db_pool)
+            .await
+        {
+            Ok(task_instance) if task_instance.status.is_finished() => Some(task_instance.status),
+            Ok(_) => None,
+            Err(_) => return,
+        };
+        let Ok(task_logs) =
+            queries::select_task_logs_after_id(&task_instance_id, last_id, &db_pool).await
+        else {
+            return;
+        };
+
+        for task_log in task_logs {
+            last_id = task_log.id;
+            let event = format!(
+                "id: {}\nevent: log\ndata: {}\n\n",
+                task_log.id,
+                serde_json::to_string(&task_log).unwrap()
+            );
+            // The client disconnected
+            if sender.send(Ok(Bytes::from(event))).await.is_err() {
+                return;
+            }
+        }
+
+        if let Some(status) = finished_status {
+            let event = format!("event: end\ndata: {}\n\n", status);
+            let _ = sender.send(Ok(Bytes::from(event))).await;
+            return;
+        }
+        time::sleep(Duration::from_secs(LOG_POLL_INTERVAL)).await;
+    }
+}
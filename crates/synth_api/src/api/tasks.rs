@@ -2,14 +2,11 @@ use crate::models::JSONResponse;
 use actix_web::{web, HttpResponse};
 use sqlx::SqlitePool;
 use synth_common::models::Task;
-use synth_common::queries;
+use synth_common::{dag, queries};
 
 /// Return a list of all Tasks
 pub async fn list(db_pool: web::Data<SqlitePool>) -> HttpResponse {
-    let tasks = sqlx::query_as!(Task, "SELECT * FROM tasks")
-        .fetch_all(db_pool.get_ref())
-        .await
-        .unwrap();
+    let tasks = queries::select_tasks(&db_pool).await.unwrap();
 
     let response_data = JSONResponse::<Task> {
         data: Some(tasks),
@@ -21,10 +18,7 @@ pub async fn list(db_pool: web::Data<SqlitePool>) -> HttpResponse {
 /// Get a specific Task
 pub async fn get(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> HttpResponse {
     let id = path.to_string();
-    let task = sqlx::query_as!(Task, "SELECT * FROM tasks WHERE id = ?", id)
-        .fetch_one(db_pool.get_ref())
-        .await
-        .unwrap();
+    let task = queries::select_task_by_id(&id, &db_pool).await.unwrap();
 
     let response_data = JSONResponse::<Task> {
         data: Some(vec![task]),
@@ -35,7 +29,56 @@ pub async fn get(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> Htt
 
 /// Create a Task
 pub async fn create(task: web::Json<Task>, db_pool: web::Data<SqlitePool>) -> HttpResponse {
-    let result = queries::upsert_task(&task.into_inner(), &db_pool).await;
+    let task = task.into_inner();
+
+    // The Task's pool has to exist, and be big enough to ever run it
+    if let Some(pool_id) = task.pool.as_deref() {
+        let error = match queries::select_pool_by_id(pool_id, &db_pool).await {
+            Ok(pool) if i64::from(task.pool_slots) > pool.slots => Some(format!(
+                "Task needs {} slot(s), but pool '{}' only has {}!",
+                task.pool_slots, pool_id, pool.slots
+            )),
+            Ok(_) if task.pool_slots < 1 => Some("A Task needs at least 1 pool slot!".to_string()),
+            Ok(_) => None,
+            Err(_) => Some(format!("Pool '{}' not found!", pool_id)),
+        };
+        if let Some(error) = error {
+            let response_data = JSONResponse::<Task> {
+                data: None,
+                errors: Some(vec![error]),
+            };
+            return HttpResponse::BadRequest().json(response_data);
+        }
+    }
+
+    // Verify that the new Task doesn't introduce a cycle into its Pipeline.
+    // Upstreams that aren't registered yet are allowed, as a manifest's
+    // Tasks are registered one at a time.
+    let mut pipeline_tasks =
+        match queries::select_task_by_pipeline_id(&task.pipeline_id, &db_pool).await {
+            Ok(pipeline_tasks) => pipeline_tasks,
+            Err(_) => {
+                let response_data = JSONResponse::<Task> {
+                    data: None,
+                    errors: Some(vec!["Failed to create the task!".to_string()]),
+                };
+                return HttpResponse::InternalServerError().json(response_data);
+            }
+        };
+    pipeline_tasks.retain(|pipeline_task| pipeline_task.id != task.id);
+    pipeline_tasks.push(task.clone());
+    if let Some(cycle) = dag::find_cycle(&pipeline_tasks) {
+        let response_data = JSONResponse::<Task> {
+            data: None,
+            errors: Some(vec![format!(
+                "Dependency cycle detected: {}",
+                cycle.join(" -> ")
+            )]),
+        };
+        return HttpResponse::BadRequest().json(response_data);
+    }
+
+    let result = queries::upsert_task(&task, &db_pool).await;
 
     match result {
         Ok(_) => {
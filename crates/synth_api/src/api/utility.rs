@@ -1,11 +1,30 @@
-use crate::models::JSONResponse;
-use actix_web::HttpResponse;
+use crate::models::{Health, JSONResponse};
+use actix_web::{web, HttpResponse};
+use chrono::Utc;
+use sqlx::SqlitePool;
+use synth_common::models::LeaderLease;
+use synth_common::{queries, schedule};
 
-/// Standard health endpoint
-pub async fn health() -> HttpResponse {
-    let response_data = JSONResponse::<String> {
-        data: Some(vec!["Feeling healthy!".to_string()]),
-        errors: None,
-    };
-    HttpResponse::Ok().json(response_data)
+/// Standard health endpoint, also showing which scheduler is the leader
+pub async fn health(db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    let now = schedule::format_timestamp(&Utc::now());
+    match queries::select_leader_lease(LeaderLease::SCHEDULER, &now, &db_pool).await {
+        Ok(scheduler_leader) => {
+            let response_data = JSONResponse::<Health> {
+                data: Some(vec![Health {
+                    status: "Feeling healthy!".to_string(),
+                    scheduler_leader,
+                }]),
+                errors: None,
+            };
+            HttpResponse::Ok().json(response_data)
+        }
+        Err(_) => {
+            let response_data = JSONResponse::<Health> {
+                data: None,
+                errors: Some(vec!["Failed to reach the database!".to_string()]),
+            };
+            HttpResponse::InternalServerError().json(response_data)
+        }
+    }
 }
@@ -0,0 +1,380 @@
+use crate::models::{JSONResponse, WorkerPoll};
+use actix_web::{web, HttpResponse};
+use chrono::{Duration as ChronoDuration, Utc};
+use sqlx::SqlitePool;
+use synth_common::config::WorkerSettings;
+use synth_common::models::{
+    RegisterWorker, TaskAssignment, TaskInstance, TaskLog, TaskResult, TaskStatus, Worker,
+    WorkerHeartbeat,
+};
+use synth_common::{queries, schedule};
+use tokio::time::{self, Duration};
+use uuid::Uuid;
+
+/// Seconds that a Worker waits for a Task when polling, unless it asks otherwise
+const DEFAULT_POLL_WAIT: u64 = 20;
+
+/// Upper bound on the seconds that a Worker may wait for a Task when polling
+const MAX_POLL_WAIT: u64 = 60;
+
+/// Seconds between attempts to claim a Task while a Worker is polling
+const CLAIM_POLL_INTERVAL: u64 = 1;
+
+/// Time until which a Worker's lease taken or renewed now is held
+fn lease_expires_at(worker_settings: &WorkerSettings) -> String {
+    let lease_duration =
+        ChronoDuration::seconds(i64::try_from(worker_settings.lease_duration).unwrap_or(i64::MAX));
+    schedule::format_timestamp(&(Utc::now() + lease_duration))
+}
+
+fn worker_not_found<T>(id: &str) -> HttpResponse
+where
+    T: serde::Serialize,
+{
+    let response_data = JSONResponse::<T> {
+        data: None,
+        errors: Some(vec![format!("Worker '{}' not found!", id)]),
+    };
+    HttpResponse::NotFound().json(response_data)
+}
+
+/// Check that a Worker holds the lease on a running TaskInstance
+async fn is_leased_to(task_instance_id: &str, worker_id: &str, db_pool: &SqlitePool) -> bool {
+    queries::select_task_instance_lease_owner(task_instance_id, db_pool)
+        .await
+        .is_ok_and(|lease_owner| lease_owner.as_deref() == Some(worker_id))
+}
+
+/// Return a list of all registered Workers
+pub async fn list(db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    match queries::select_workers(&db_pool).await {
+        Ok(workers) => {
+            let response_data = JSONResponse::<Worker> {
+                data: Some(workers),
+                errors: None,
+            };
+            HttpResponse::Ok().json(response_data)
+        }
+        Err(_) => {
+            let response_data = JSONResponse::<Worker> {
+                data: None,
+                errors: Some(vec!["Failed to get workers!".to_string()]),
+            };
+            HttpResponse::InternalServerError().json(response_data)
+        }
+    }
+}
+
+/// Register a new Worker, giving it the ID it uses for every other call
+pub async fn register(
+    register: web::Json<RegisterWorker>,
+    db_pool: web::Data<SqlitePool>,
+) -> HttpResponse {
+    let now = schedule::format_timestamp(&Utc::now());
+    let worker = Worker {
+        id: Uuid::new_v4().to_string(),
+        hostname: register.into_inner().hostname,
+        registered_at: now.clone(),
+        last_heartbeat_at: now,
+    };
+
+    match queries::insert_worker(&worker, &db_pool).await {
+        Ok(_) => {
+            let response_data = JSONResponse::<Worker> {
+                data: Some(vec![worker]),
+                errors: None,
+            };
+            HttpResponse::Created().json(response_data)
+        }
+        Err(_) => {
+            let response_data = JSONResponse::<Worker> {
+                data: None,
+                errors: Some(vec!["Failed to register the worker!".to_string()]),
+            };
+            HttpResponse::InternalServerError().json(response_data)
+        }
+    }
+}
+
+/// Keep a Worker and the leases on its TaskInstances alive.
+///
+/// Responds with the IDs of the TaskInstances the Worker reported as running
+/// that it must stop, because they were cancelled or marked as zombies.
+pub async fn heartbeat(
+    path: web::Path<String>,
+    heartbeat: web::Json<WorkerHeartbeat>,
+    db_pool: web::Data<SqlitePool>,
+    worker_settings: web::Data<WorkerSettings>,
+) -> HttpResponse {
+    let id = path.to_string();
+    let now = schedule::format_timestamp(&Utc::now());
+    match queries::update_worker_heartbeat(&id, &now, &db_pool).await {
+        Ok(true) => {}
+        Ok(false) => return worker_not_found::<String>(&id),
+        Err(_) => {
+            let response_data = JSONResponse::<String> {
+                data: None,
+                errors: Some(vec!["Failed to record the heartbeat!".to_string()]),
+            };
+            return HttpResponse::InternalServerError().json(response_data);
+        }
+    }
+    let running = heartbeat.into_inner().running;
+    let renewed = match queries::renew_task_instance_leases(
+        &running,
+        &id,
+        &now,
+        &lease_expires_at(&worker_settings),
+        &db_pool,
+    )
+    .await
+    {
+        Ok(renewed) => renewed,
+        Err(_) => {
+            let response_data = JSONResponse::<String> {
+                data: None,
+                errors: Some(vec!["Failed to renew the leases!".to_string()]),
+            };
+            return HttpResponse::InternalServerError().json(response_data);
+        }
+    };
+    let to_stop: Vec<String> = running
+        .into_iter()
+        .filter(|task_instance_id| !renewed.contains(task_instance_id))
+        .collect();
+    let response_data = JSONResponse::<String> {
+        data: Some(to_stop),
+        errors: None,
+    };
+    HttpResponse::Ok().json(response_data)
+}
+
+/// Long-poll for a queued TaskInstance, claiming it for the Worker.
+///
+/// Responds with a single TaskAssignment, or none if nothing was queued
+/// within the wait.
+pub async fn poll(
+    path: web::Path<String>,
+    query: web::Query<WorkerPoll>,
+    db_pool: web::Data<SqlitePool>,
+    worker_settings: web::Data<WorkerSettings>,
+) -> HttpResponse {
+    let id = path.to_string();
+    if queries::select_worker_by_id(&id, &db_pool).await.is_err() {
+        return worker_not_found::<TaskAssignment>(&id);
+    }
+    let wait = query.wait.unwrap_or(DEFAULT_POLL_WAIT).min(MAX_POLL_WAIT);
+    let deadline = time::Instant::now() + Duration::from_secs(wait);
+
+    loop {
+        let now = schedule::format_timestamp(&Utc::now());
+        // Polling shows that the Worker is alive
+        if queries::update_worker_heartbeat(&id, &now, &db_pool)
+            .await
+            .is_err()
+        {
+            let response_data = JSONResponse::<TaskAssignment> {
+                data: None,
+                errors: Some(vec!["Failed to record the heartbeat!".to_string()]),
+            };
+            return HttpResponse::InternalServerError().json(response_data);
+        }
+        let claimed =
+            queries::claim_task_instance(&id, &now, &lease_expires_at(&worker_settings), &db_pool)
+                .await;
+        match claimed {
+            Ok(Some(task_instance)) => {
+                let task_instance_id = task_instance.id.clone();
+                return match queries::select_task_assignment(task_instance, &db_pool).await {
+                    Ok(assignment) => {
+                        let response_data = JSONResponse::<TaskAssignment> {
+                            data: Some(vec![assignment]),
+                            errors: None,
+                        };
+                        HttpResponse::Ok().json(response_data)
+                    }
+                    Err(_) => {
+                        // Don't leave the TaskInstance stuck as running
+                        let execution_end = schedule::format_timestamp(&Utc::now());
+                        let _ = queries::finish_task_instance(
+                            &task_instance_id,
+                            &id,
+                            TaskStatus::Failed,
+                            None,
+                            &execution_end,
+                            &db_pool,
+                        )
+                        .await;
+                        let response_data = JSONResponse::<TaskAssignment> {
+                            data: None,
+                            errors: Some(vec![format!(
+                                "Task instance '{}' can't be run!",
+                                task_instance_id
+                            )]),
+                        };
+                        HttpResponse::InternalServerError().json(response_data)
+                    }
+                };
+            }
+            Ok(None) if time::Instant::now() < deadline => {
+                time::sleep(Duration::from_secs(CLAIM_POLL_INTERVAL)).await;
+            }
+            Ok(None) => {
+                let response_data = JSONResponse::<TaskAssignment> {
+                    data: Some(vec![]),
+                    errors: None,
+                };
+                return HttpResponse::Ok().json(response_data);
+            }
+            Err(_) => {
+                let response_data = JSONResponse::<TaskAssignment> {
+                    data: None,
+                    errors: Some(vec!["Failed to claim a task instance!".to_string()]),
+                };
+                return HttpResponse::InternalServerError().json(response_data);
+            }
+        }
+    }
+}
+
+/// Append log lines sent by the Worker running a TaskInstance
+pub async fn write_logs(
+    path: web::Path<(String, String)>,
+    task_logs: web::Json<Vec<TaskLog>>,
+    db_pool: web::Data<SqlitePool>,
+) -> HttpResponse {
+    let (id, task_instance_id) = path.into_inner();
+    if !is_leased_to(&task_instance_id, &id, &db_pool).await {
+        let response_data = JSONResponse::<TaskLog> {
+            data: None,
+            errors: Some(vec![format!(
+                "Worker '{}' isn't running task instance '{}'!",
+                id, task_instance_id
+            )]),
+        };
+        return HttpResponse::Conflict().json(response_data);
+    }
+
+    let task_logs: Vec<TaskLog> = task_logs
+        .into_inner()
+        .into_iter()
+        .map(|task_log| TaskLog {
+            task_instance_id: task_instance_id.clone(),
+            ..task_log
+        })
+        .collect();
+    match queries::insert_task_logs(&task_logs, &db_pool).await {
+        Ok(_) => {
+            let response_data = JSONResponse::<TaskLog> {
+                data: Some(vec![]),
+                errors: None,
+            };
+            HttpResponse::Created().json(response_data)
+        }
+        Err(_) => {
+            let response_data = JSONResponse::<TaskLog> {
+                data: None,
+                errors: Some(vec!["Failed to save the task logs!".to_string()]),
+            };
+            HttpResponse::InternalServerError().json(response_data)
+        }
+    }
+}
+
+/// Record how a TaskInstance run by the Worker ended.
+///
+/// A Worker may only report that its TaskInstance succeeded or failed, other
+/// outcomes are decided by the server.
+pub async fn report_result(
+    path: web::Path<(String, String)>,
+    result: web::Json<TaskResult>,
+    db_pool: web::Data<SqlitePool>,
+) -> HttpResponse {
+    let (id, task_instance_id) = path.into_inner();
+    let result = result.into_inner();
+    if !matches!(result.status, TaskStatus::Success | TaskStatus::Failed) {
+        let response_data = JSONResponse::<TaskInstance> {
+            data: None,
+            errors: Some(vec![format!(
+                "A task instance can't be reported as {}!",
+                result.status
+            )]),
+        };
+        return HttpResponse::BadRequest().json(response_data);
+    }
+    let Ok(task_instance) = queries::select_task_instance_by_id(&task_instance_id, &db_pool).await
+    else {
+        let response_data = JSONResponse::<TaskInstance> {
+            data: None,
+            errors: Some(vec![format!(
+                "Task instance '{}' not found!",
+                task_instance_id
+            )]),
+        };
+        return HttpResponse::NotFound().json(response_data);
+    };
+
+    let execution_end = schedule::format_timestamp(&Utc::now());
+    let finished = if task_instance.status.can_transition_to(result.status) {
+        queries::finish_task_instance(
+            &task_instance_id,
+            &id,
+            result.status,
+            result.exit_code,
+            &execution_end,
+            &db_pool,
+        )
+        .await
+    } else {
+        Ok(false)
+    };
+
+    match finished {
+        Ok(true) => {}
+        Ok(false) => {
+            // A cancelled TaskInstance still records how its process ended
+            if task_instance.status == TaskStatus::Cancelled {
+                let _ = queries::finish_cancelled_task_instance(
+                    &task_instance_id,
+                    &id,
+                    result.exit_code,
+                    &execution_end,
+                    &db_pool,
+                )
+                .await;
+            }
+            let response_data = JSONResponse::<TaskInstance> {
+                data: None,
+                errors: Some(vec![format!(
+                    "Worker '{}' can't finish task instance '{}' as {}!",
+                    id, task_instance_id, result.status
+                )]),
+            };
+            return HttpResponse::Conflict().json(response_data);
+        }
+        Err(_) => {
+            let response_data = JSONResponse::<TaskInstance> {
+                data: None,
+                errors: Some(vec!["Failed to save the task result!".to_string()]),
+            };
+            return HttpResponse::InternalServerError().json(response_data);
+        }
+    }
+
+    match queries::select_task_instance_by_id(&task_instance_id, &db_pool).await {
+        Ok(task_instance) => {
+            let response_data = JSONResponse::<TaskInstance> {
+                data: Some(vec![task_instance]),
+                errors: None,
+            };
+            HttpResponse::Ok().json(response_data)
+        }
+        Err(_) => {
+            let response_data = JSONResponse::<TaskInstance> {
+                data: None,
+                errors: Some(vec!["Failed to get the task instance!".to_string()]),
+            };
+            HttpResponse::InternalServerError().json(response_data)
+        }
+    }
+}
@@ -1,4 +1,6 @@
-use crate::api::{pipelines, task_instances, tasks, utility};
+use crate::api::{
+    calendars, pipeline_runs, pipelines, pools, task_instances, tasks, utility, workers,
+};
 use crate::models::JSONResponse;
 use crate::views;
 use actix_web::{http::Method, web, HttpResponse, Route};
@@ -37,11 +39,46 @@ pub fn get_endpoints() -> Vec<Endpoint> {
             method: Method::GET,
             route: web::get().to(views::pipelines::index),
         },
+        Endpoint {
+            path: "/pipelines/{id}",
+            method: Method::GET,
+            route: web::get().to(views::pipelines::runs),
+        },
+        Endpoint {
+            path: "/pipelines/{id}/trigger",
+            method: Method::POST,
+            route: web::post().to(views::pipelines::trigger),
+        },
+        Endpoint {
+            path: "/pipelines/{id}/pause",
+            method: Method::POST,
+            route: web::post().to(views::pipelines::pause),
+        },
+        Endpoint {
+            path: "/pipelines/{id}/resume",
+            method: Method::POST,
+            route: web::post().to(views::pipelines::resume),
+        },
+        Endpoint {
+            path: "/pipeline_runs/{id}",
+            method: Method::GET,
+            route: web::get().to(views::pipeline_runs::show),
+        },
+        Endpoint {
+            path: "/task_instances/{id}/clear",
+            method: Method::POST,
+            route: web::post().to(views::task_instances::clear),
+        },
         Endpoint {
             path: "/tasks",
             method: Method::GET,
             route: web::get().to(views::tasks::index),
         },
+        Endpoint {
+            path: "/pools",
+            method: Method::GET,
+            route: web::get().to(views::pools::index),
+        },
         // Generic
         Endpoint {
             path: "/api/health",
@@ -69,6 +106,42 @@ pub fn get_endpoints() -> Vec<Endpoint> {
             method: Method::POST,
             route: web::post().to(pipelines::create),
         },
+        Endpoint {
+            path: "/api/pipelines/{id}/backfill",
+            method: Method::POST,
+            route: web::post().to(pipelines::backfill),
+        },
+        Endpoint {
+            path: "/api/pipelines/{id}/runs",
+            method: Method::GET,
+            route: web::get().to(pipelines::runs),
+        },
+        Endpoint {
+            path: "/api/pipelines/{id}/trigger",
+            method: Method::POST,
+            route: web::post().to(pipelines::trigger),
+        },
+        Endpoint {
+            path: "/api/pipelines/{id}/pause",
+            method: Method::POST,
+            route: web::post().to(pipelines::pause),
+        },
+        Endpoint {
+            path: "/api/pipelines/{id}/resume",
+            method: Method::POST,
+            route: web::post().to(pipelines::resume),
+        },
+        // Pipeline Runs
+        Endpoint {
+            path: "/api/pipeline_runs/{id}",
+            method: Method::GET,
+            route: web::get().to(pipeline_runs::get),
+        },
+        Endpoint {
+            path: "/api/pipeline_runs/{id}/cancel",
+            method: Method::POST,
+            route: web::post().to(pipeline_runs::cancel),
+        },
         // Tasks
         Endpoint {
             path: "/api/tasks",
@@ -85,6 +158,33 @@ pub fn get_endpoints() -> Vec<Endpoint> {
             method: Method::GET,
             route: web::get().to(tasks::get),
         },
+        // Pools
+        Endpoint {
+            path: "/api/pools",
+            method: Method::GET,
+            route: web::get().to(pools::list),
+        },
+        Endpoint {
+            path: "/api/pools",
+            method: Method::POST,
+            route: web::post().to(pools::create),
+        },
+        Endpoint {
+            path: "/api/pools/{id}",
+            method: Method::GET,
+            route: web::get().to(pools::get),
+        },
+        // Calendars
+        Endpoint {
+            path: "/api/calendars",
+            method: Method::GET,
+            route: web::get().to(calendars::list),
+        },
+        Endpoint {
+            path: "/api/calendars/{id}",
+            method: Method::GET,
+            route: web::get().to(calendars::get),
+        },
         // Task Instances
         Endpoint {
             path: "/api/task_instances",
@@ -96,6 +196,57 @@ pub fn get_endpoints() -> Vec<Endpoint> {
             method: Method::GET,
             route: web::get().to(task_instances::get),
         },
+        Endpoint {
+            path: "/api/task_instances/{id}/cancel",
+            method: Method::POST,
+            route: web::post().to(task_instances::cancel),
+        },
+        Endpoint {
+            path: "/api/task_instances/{id}/clear",
+            method: Method::POST,
+            route: web::post().to(task_instances::clear),
+        },
+        Endpoint {
+            path: "/api/task_instances/{id}/logs",
+            method: Method::GET,
+            route: web::get().to(task_instances::logs),
+        },
+        Endpoint {
+            path: "/api/task_instances/{id}/logs/stream",
+            method: Method::GET,
+            route: web::get().to(task_instances::stream_logs),
+        },
+        // Workers
+        Endpoint {
+            path: "/api/workers",
+            method: Method::GET,
+            route: web::get().to(workers::list),
+        },
+        Endpoint {
+            path: "/api/workers",
+            method: Method::POST,
+            route: web::post().to(workers::register),
+        },
+        Endpoint {
+            path: "/api/workers/{id}/heartbeat",
+            method: Method::POST,
+            route: web::post().to(workers::heartbeat),
+        },
+        Endpoint {
+            path: "/api/workers/{id}/task",
+            method: Method::GET,
+            route: web::get().to(workers::poll),
+        },
+        Endpoint {
+            path: "/api/workers/{id}/task_instances/{task_instance_id}/logs",
+            method: Method::POST,
+            route: web::post().to(workers::write_logs),
+        },
+        Endpoint {
+            path: "/api/workers/{id}/task_instances/{task_instance_id}/result",
+            method: Method::POST,
+            route: web::post().to(workers::report_result),
+        },
     ]
 }
 
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use synth_common::models::Task;
+use synth_common::models::{LeaderLease, Task};
 
 /// Generic Struct used for API Responses
 #[derive(Deserialize, Serialize, PartialEq, Debug)]
@@ -9,3 +9,24 @@ pub struct JSONResponse<T> {
 }
 
 pub type TaskJSONResponse = JSONResponse<Task>;
+
+/// Health of the server, along with the scheduler that is in charge
+#[derive(Deserialize, Serialize, PartialEq, Debug, Default)]
+pub struct Health {
+    pub status: String,
+    /// Lease of the leading scheduler, unset if no scheduler is running
+    pub scheduler_leader: Option<LeaderLease>,
+}
+
+/// Query parameters used to filter a list of TaskInstances
+#[derive(Deserialize, Serialize, PartialEq, Debug, Default)]
+pub struct TaskInstanceFilter {
+    pub status: Option<String>,
+}
+
+/// Query parameters used by a Worker polling for a TaskInstance to run
+#[derive(Deserialize, Serialize, PartialEq, Debug, Default)]
+pub struct WorkerPoll {
+    /// Seconds to wait for a queued TaskInstance before giving up
+    pub wait: Option<u64>,
+}
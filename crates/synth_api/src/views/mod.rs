@@ -1,4 +1,6 @@
+pub mod pipeline_runs;
 pub mod pipelines;
+pub mod pools;
 pub mod task_instances;
 pub mod tasks;
 
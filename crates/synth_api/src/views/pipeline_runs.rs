@@ -0,0 +1,28 @@
+use actix_web::{web, HttpResponse};
+use askama::Template;
+use sqlx::SqlitePool;
+use synth_common::models::{PipelineRun, TaskInstance};
+use synth_common::queries;
+
+#[derive(Template)]
+#[template(path = "pipeline_runs/show.html")]
+struct Show {
+    pipeline_run: PipelineRun,
+    task_instances: Vec<TaskInstance>,
+}
+
+pub async fn show(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    let id = path.to_string();
+    let Ok(pipeline_run) = queries::select_pipeline_run_by_id(&id, &db_pool).await else {
+        return HttpResponse::NotFound().body(format!("Pipeline run '{}' not found!", id));
+    };
+    let task_instances = queries::select_task_instances_by_pipeline_run_id(&id, &db_pool)
+        .await
+        .unwrap();
+    let show_template = Show {
+        pipeline_run,
+        task_instances,
+    };
+    let rendered_html = show_template.render().unwrap();
+    HttpResponse::Ok().body(rendered_html)
+}
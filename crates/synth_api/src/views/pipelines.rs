@@ -1,8 +1,26 @@
+use actix_web::http::header;
 use actix_web::{web, HttpResponse};
 use askama::Template;
+use chrono::Utc;
 use sqlx::SqlitePool;
-use synth_common::models::Pipeline;
-use synth_common::queries;
+use synth_common::models::{Pipeline, PipelineRun, PipelineRunStatus, RunTrigger};
+use synth_common::{queries, schedule};
+use uuid::Uuid;
+
+mod filters {
+    use synth_common::schedule;
+
+    /// Show a timestamp on the wall clock of a Pipeline's time zone
+    pub fn local_time<T: std::fmt::Display>(
+        timestamp: T,
+        timezone: &Option<String>,
+    ) -> askama::Result<String> {
+        Ok(schedule::format_local_timestamp(
+            &timestamp.to_string(),
+            timezone.as_deref(),
+        ))
+    }
+}
 
 #[derive(Template)]
 #[template(path = "pipelines/index.html")]
@@ -16,3 +34,72 @@ pub async fn index(db_pool: web::Data<SqlitePool>) -> HttpResponse {
     let rendered_html = index_template.render().unwrap();
     HttpResponse::Ok().body(rendered_html)
 }
+
+#[derive(Template)]
+#[template(path = "pipelines/runs.html")]
+struct Runs {
+    pipeline: Pipeline,
+    pipeline_runs: Vec<PipelineRun>,
+}
+
+pub async fn runs(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    let id = path.to_string();
+    let Ok(pipeline) = queries::select_pipeline_by_id(&id, &db_pool).await else {
+        return HttpResponse::NotFound().body(format!("Pipeline '{}' not found!", id));
+    };
+    let pipeline_runs = queries::select_pipeline_runs_by_pipeline_id(&id, &db_pool)
+        .await
+        .unwrap();
+    let runs_template = Runs {
+        pipeline,
+        pipeline_runs,
+    };
+    let rendered_html = runs_template.render().unwrap();
+    HttpResponse::Ok().body(rendered_html)
+}
+
+/// Queue a manual run of a Pipeline from the "Run now" button
+pub async fn trigger(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    let id = path.to_string();
+    if queries::select_pipeline_by_id(&id, &db_pool).await.is_err() {
+        return HttpResponse::NotFound().body(format!("Pipeline '{}' not found!", id));
+    }
+    let pipeline_run = PipelineRun {
+        id: Uuid::new_v4().to_string(),
+        pipeline_id: id.clone(),
+        scheduled_time: schedule::format_timestamp(&Utc::now()),
+        trigger: RunTrigger::Manual,
+        status: PipelineRunStatus::Queued,
+        created_at: schedule::format_timestamp(&Utc::now()),
+        ..Default::default()
+    };
+    queries::insert_pipeline_run(&pipeline_run, &db_pool)
+        .await
+        .unwrap();
+
+    HttpResponse::SeeOther()
+        .insert_header((header::LOCATION, format!("/pipelines/{}", id)))
+        .finish()
+}
+
+/// Pause a Pipeline from the pipelines page
+pub async fn pause(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    queries::set_pipeline_paused(&path, true, &db_pool)
+        .await
+        .unwrap();
+    redirect_to_index()
+}
+
+/// Resume a Pipeline from the pipelines page
+pub async fn resume(path: web::Path<String>, db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    queries::set_pipeline_paused(&path, false, &db_pool)
+        .await
+        .unwrap();
+    redirect_to_index()
+}
+
+fn redirect_to_index() -> HttpResponse {
+    HttpResponse::SeeOther()
+        .insert_header((header::LOCATION, "/pipelines"))
+        .finish()
+}
@@ -0,0 +1,18 @@
+use actix_web::{web, HttpResponse};
+use askama::Template;
+use sqlx::SqlitePool;
+use synth_common::models::ResourcePool;
+use synth_common::queries;
+
+#[derive(Template)]
+#[template(path = "pools/index.html")]
+struct Index {
+    pools: Vec<ResourcePool>,
+}
+
+pub async fn index(db_pool: web::Data<SqlitePool>) -> HttpResponse {
+    let pools = queries::select_pools(&db_pool).await.unwrap();
+    let index_template = Index { pools };
+    let rendered_html = index_template.render().unwrap();
+    HttpResponse::Ok().body(rendered_html)
+}
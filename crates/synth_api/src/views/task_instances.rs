@@ -1,7 +1,10 @@
+use crate::api::task_instances::clear_task_instance;
+use actix_web::http::header;
 use actix_web::{web, HttpResponse};
 use askama::Template;
 use sqlx::SqlitePool;
-use synth_common::models::TaskInstance;
+use synth_common::models::{Clear, TaskInstance};
+use synth_common::queries;
 
 #[derive(Template)]
 #[template(path = "task_instances/index.html")]
@@ -10,8 +13,7 @@ struct Index {
 }
 
 pub async fn index(db_pool: web::Data<SqlitePool>) -> HttpResponse {
-    let task_instances = sqlx::query_as!(TaskInstance, "SELECT * FROM task_instances")
-        .fetch_all(db_pool.get_ref())
+    let task_instances = queries::select_task_instances(None, &db_pool)
         .await
         .unwrap();
     let index_template = Index { task_instances };
@@ -19,3 +21,31 @@ pub async fn index(db_pool: web::Data<SqlitePool>) -> HttpResponse {
     let rendered_html = index_template.render().unwrap();
     HttpResponse::Ok().body(rendered_html)
 }
+
+/// Clear a TaskInstance from the "Clear" buttons on the pipeline run page
+pub async fn clear(
+    path: web::Path<String>,
+    clear: web::Form<Clear>,
+    db_pool: web::Data<SqlitePool>,
+) -> HttpResponse {
+    let id = path.to_string();
+    let Ok(task_instance) = queries::select_task_instance_by_id(&id, &db_pool).await else {
+        return HttpResponse::NotFound().body(format!("Task instance '{}' not found!", id));
+    };
+    let Some(pipeline_run_id) = task_instance.pipeline_run_id.clone() else {
+        return HttpResponse::BadRequest().body(format!(
+            "Task instance '{}' doesn't belong to a pipeline run!",
+            id
+        ));
+    };
+    clear_task_instance(&task_instance, clear.downstream, &db_pool)
+        .await
+        .unwrap();
+
+    HttpResponse::SeeOther()
+        .insert_header((
+            header::LOCATION,
+            format!("/pipeline_runs/{}", pipeline_run_id),
+        ))
+        .finish()
+}
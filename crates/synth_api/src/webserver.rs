@@ -1,18 +1,28 @@
 use super::endpoints::get_endpoints;
 use actix_web::dev::Server;
 use actix_web::{web, App, HttpServer};
+use sqlx::types::Json;
 use sqlx::SqlitePool;
+use std::collections::HashMap;
 use std::net::TcpListener;
-use synth_common::{database, telemetry};
+use synth_common::config::WorkerSettings;
+use synth_common::models::{Calendar, ResourcePool};
+use synth_common::{calendar, config, database, queries, telemetry};
 use tracing_actix_web::TracingLogger;
 
 /// Configure and return a Server instance to be awaited
-pub fn run_webserver(listener: TcpListener, pool: SqlitePool) -> Result<Server, anyhow::Error> {
+pub fn run_webserver(
+    listener: TcpListener,
+    pool: SqlitePool,
+    worker_settings: WorkerSettings,
+) -> Result<Server, anyhow::Error> {
     let pool = web::Data::new(pool);
+    let worker_settings = web::Data::new(worker_settings);
     let server = HttpServer::new(move || {
         // Build the App from the endpoint vector
         let mut app = App::new()
             .app_data(pool.clone())
+            .app_data(worker_settings.clone())
             // Enable tracing spans within handlers
             .wrap(TracingLogger::default());
         for endpoint in get_endpoints() {
@@ -25,16 +35,64 @@ pub fn run_webserver(listener: TcpListener, pool: SqlitePool) -> Result<Server,
     Ok(server)
 }
 
+/// Create or resize the pools defined in the config, failing on the first
+/// pool that is too small for the Tasks in it
+async fn register_pools(
+    pools: HashMap<String, u32>,
+    db_pool: &SqlitePool,
+) -> Result<(), config::ConfigError> {
+    for (id, slots) in pools {
+        let pool = ResourcePool {
+            id,
+            slots: i64::from(slots),
+            ..Default::default()
+        };
+        let saved = queries::upsert_pool(&pool, db_pool)
+            .await
+            .map_err(|e| config::ConfigError::Foreign(Box::new(e)))?;
+        if !saved {
+            return Err(config::ConfigError::Message(format!(
+                "Pool '{}' has Tasks that need more than {} slot(s)!",
+                pool.id, pool.slots
+            )));
+        }
+    }
+    Ok(())
+}
+
 /// Setup for the webserver
 pub async fn start_webserver() {
     telemetry::init_logging();
+    let settings = config::load_config("synth.toml").expect("Failed to load the config!");
     let api_pool = database::get_db_pool().await;
 
+    // Register the pools defined in the config, updating their slots
+    if let Err(e) = register_pools(settings.pools, &api_pool).await {
+        println!("> Failed to register the pools! {}", e);
+        std::process::exit(2)
+    }
+
+    // Register the calendars defined in the config, replacing their holidays
+    for (id, calendar_settings) in settings.calendars {
+        let holidays = calendar::load_holidays(&calendar_settings)
+            .unwrap_or_else(|e| panic!("Failed to load calendar '{}'! {}", id, e));
+        let calendar = Calendar {
+            id,
+            holidays: Json(holidays),
+        };
+        queries::upsert_calendar(&calendar, &api_pool)
+            .await
+            .expect("Failed to register the calendars!");
+    }
+
     // Prepare values to configure the server
     let server_address = "127.0.0.1:8080";
     let listener = TcpListener::bind(server_address).expect("Failed to bind port!");
 
     // Run the server
     println!("> Starting the webserver at address: {}", server_address);
-    run_webserver(listener, api_pool).unwrap().await.unwrap();
+    run_webserver(listener, api_pool, settings.worker)
+        .unwrap()
+        .await
+        .unwrap();
 }
@@ -1,9 +1,11 @@
 mod helpers;
 
-use crate::helpers::spawn_app;
+use crate::helpers::{spawn_app, spawn_app_with_pool};
 use pretty_assertions::assert_eq;
 use reqwest::{Client, StatusCode};
-use synth_api::models::JSONResponse;
+use synth_api::models::{Health, JSONResponse};
+use synth_common::models::LeaderLease;
+use synth_common::queries;
 
 #[tokio::test]
 async fn health_success() {
@@ -21,11 +23,95 @@ async fn health_success() {
     assert_eq!(response.status(), StatusCode::OK);
 
     // Verify the Response Data
-    let body: JSONResponse<String> = response
+    let body: JSONResponse<Health> = response
         .json()
         .await
         .expect("Failed to parse the health response!");
-    assert_eq!(body.data, Some(vec!["Feeling healthy!".to_string()]));
+    assert_eq!(
+        body.data,
+        Some(vec![Health {
+            status: "Feeling healthy!".to_string(),
+            scheduler_leader: None,
+        }])
+    );
+}
+
+#[tokio::test]
+async fn health_shows_scheduler_leader() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    let url = &format!("{}/api/health", server_address);
+    for holder in ["scheduler1", "scheduler2"] {
+        queries::acquire_leader_lease(
+            LeaderLease::SCHEDULER,
+            holder,
+            "2023-12-20T09:00:00Z",
+            "9999-01-01T00:00:00Z",
+            &db_pool,
+        )
+        .await
+        .expect("Failed to acquire the leader lease!");
+    }
+
+    // Act
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .expect("Failed to send request!");
+
+    // Assert that the lease still belongs to the first scheduler
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: JSONResponse<Health> = response.json().await.unwrap();
+    let scheduler_leader = body.data.unwrap().remove(0).scheduler_leader.unwrap();
+    assert_eq!(scheduler_leader.holder, "scheduler1");
+}
+
+#[tokio::test]
+async fn expired_leader_lease_is_taken_over() {
+    // Arrange
+    let (_, db_pool) = spawn_app_with_pool().await;
+    queries::acquire_leader_lease(
+        LeaderLease::SCHEDULER,
+        "scheduler1",
+        "2023-12-20T09:00:00Z",
+        "2023-12-20T09:00:30Z",
+        &db_pool,
+    )
+    .await
+    .unwrap();
+
+    // Act
+    let renewed_too_late = queries::acquire_leader_lease(
+        LeaderLease::SCHEDULER,
+        "scheduler2",
+        "2023-12-20T09:00:20Z",
+        "2023-12-20T09:00:50Z",
+        &db_pool,
+    )
+    .await
+    .unwrap();
+    let taken_over = queries::acquire_leader_lease(
+        LeaderLease::SCHEDULER,
+        "scheduler2",
+        "2023-12-20T09:00:40Z",
+        "2023-12-20T09:01:10Z",
+        &db_pool,
+    )
+    .await
+    .unwrap();
+
+    // Assert
+    assert!(!renewed_too_late);
+    assert!(taken_over);
+    let leader_lease =
+        queries::select_leader_lease(LeaderLease::SCHEDULER, "2023-12-20T09:00:40Z", &db_pool)
+            .await
+            .unwrap()
+            .unwrap();
+    assert_eq!(leader_lease.holder, "scheduler2");
+    assert_eq!(leader_lease.acquired_at, "2023-12-20T09:00:40Z");
 }
 
 #[tokio::test]
@@ -0,0 +1,183 @@
+mod helpers;
+
+use crate::helpers::{spawn_app, spawn_app_with_pool};
+use pretty_assertions::assert_eq;
+use reqwest::{Client, StatusCode};
+use sqlx::types::Json;
+use sqlx::SqlitePool;
+use synth_api::models::JSONResponse;
+use synth_common::{models, queries};
+
+/// Register a calendar with Friday 2024-07-05 as its only holiday
+async fn create_calendar(db_pool: &SqlitePool) -> models::Calendar {
+    let calendar = models::Calendar {
+        id: "nyse".to_owned(),
+        holidays: Json(vec!["2024-07-05".to_owned()]),
+    };
+    queries::upsert_calendar(&calendar, db_pool)
+        .await
+        .expect("Failed to insert calendar!");
+    calendar
+}
+
+#[tokio::test]
+async fn list_and_get_calendars_success() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    let calendar = create_calendar(&db_pool).await;
+
+    // Act
+    let list_response = client
+        .get(format!("{}/api/calendars", server_address))
+        .send()
+        .await
+        .expect("Failed to GET calendars!");
+    let get_response = client
+        .get(format!("{}/api/calendars/nyse", server_address))
+        .send()
+        .await
+        .expect("Failed to GET calendar!");
+    let missing_response = client
+        .get(format!("{}/api/calendars/lse", server_address))
+        .send()
+        .await
+        .expect("Failed to GET calendar!");
+
+    // Assert
+    assert_eq!(list_response.status(), StatusCode::OK);
+    let list_body: JSONResponse<models::Calendar> = list_response.json().await.unwrap();
+    assert_eq!(list_body.data, Some(vec![calendar.clone()]));
+    assert_eq!(get_response.status(), StatusCode::OK);
+    let get_body: JSONResponse<models::Calendar> = get_response.json().await.unwrap();
+    assert_eq!(get_body.data, Some(vec![calendar]));
+    assert_eq!(missing_response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn create_pipeline_with_unknown_calendar_fails() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let pipeline = models::Pipeline {
+        id: "financepipeline".to_owned(),
+        schedule: "0 9 * * 1-5".to_owned(),
+        calendar: Some("lse".to_owned()),
+        ..Default::default()
+    };
+
+    // Act
+    let response = client
+        .post(format!("{}/api/pipelines", server_address))
+        .json(&pipeline)
+        .send()
+        .await
+        .expect("Failed to POST pipeline!");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn backfill_pipeline_on_calendar_avoids_holidays() {
+    // Runs on the 5th and 6th of July 2024, a Friday holiday and a Saturday
+    let cases = vec![
+        (
+            models::HolidayPolicy::SkipOnHoliday,
+            "2024-07-01T00:00:00Z",
+            vec!["2024-07-06T09:00:00Z"],
+        ),
+        (
+            models::HolidayPolicy::NextBusinessDay,
+            "2024-07-01T00:00:00Z",
+            vec!["2024-07-06T09:00:00Z", "2024-07-08T09:00:00Z"],
+        ),
+        // A run moved off a holiday before the range still lands in it
+        (
+            models::HolidayPolicy::NextBusinessDay,
+            "2024-07-07T00:00:00Z",
+            vec!["2024-07-08T09:00:00Z"],
+        ),
+    ];
+
+    for (holiday_policy, start, expected_times) in cases {
+        // Arrange
+        let (server_address, db_pool) = spawn_app_with_pool().await;
+        let client = Client::new();
+        create_calendar(&db_pool).await;
+        let pipeline = models::Pipeline {
+            id: "financepipeline".to_owned(),
+            schedule: "0 9 5,6 7 *".to_owned(),
+            calendar: Some("nyse".to_owned()),
+            holiday_policy,
+            ..Default::default()
+        };
+        client
+            .post(format!("{}/api/pipelines", server_address))
+            .json(&pipeline)
+            .send()
+            .await
+            .expect("Failed to POST pipeline!");
+
+        // Act
+        let backfill = models::Backfill {
+            start: start.to_owned(),
+            end: "2024-07-31T00:00:00Z".to_owned(),
+        };
+        let response = client
+            .post(format!(
+                "{}/api/pipelines/financepipeline/backfill",
+                server_address
+            ))
+            .json(&backfill)
+            .send()
+            .await
+            .expect("Failed to POST backfill!");
+
+        // Assert
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body: JSONResponse<models::PipelineRun> = response.json().await.unwrap();
+        let scheduled_times: Vec<String> = body
+            .data
+            .unwrap()
+            .into_iter()
+            .map(|pipeline_run| pipeline_run.scheduled_time)
+            .collect();
+        assert_eq!(scheduled_times, expected_times, "{}", holiday_policy);
+    }
+}
+
+#[tokio::test]
+async fn changing_calendar_holidays_reschedules_pipelines() {
+    // Arrange
+    let (_, db_pool) = spawn_app_with_pool().await;
+    let mut calendar = create_calendar(&db_pool).await;
+    let pipeline = models::Pipeline {
+        id: "financepipeline".to_owned(),
+        schedule: "0 9 * * 1-5".to_owned(),
+        calendar: Some("nyse".to_owned()),
+        ..Default::default()
+    };
+    queries::upsert_pipeline(&pipeline, &db_pool).await.unwrap();
+    queries::init_pipeline_next_run_at(&pipeline.id, "2024-07-08T09:00:00Z", &db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    queries::upsert_calendar(&calendar, &db_pool).await.unwrap();
+    let unchanged = queries::select_pipeline_by_id(&pipeline.id, &db_pool)
+        .await
+        .unwrap();
+    calendar.holidays.push("2024-07-08".to_owned());
+    queries::upsert_calendar(&calendar, &db_pool).await.unwrap();
+    let changed = queries::select_pipeline_by_id(&pipeline.id, &db_pool)
+        .await
+        .unwrap();
+
+    // Assert that the next run is only worked out again for new holidays
+    assert_eq!(
+        unchanged.next_run_at.as_deref(),
+        Some("2024-07-08T09:00:00Z")
+    );
+    assert_eq!(changed.next_run_at, None);
+}
@@ -10,6 +10,14 @@ use uuid::Uuid;
 /// will automatically be destroyed and cleaned when the
 /// process ends.
 pub async fn spawn_app() -> String {
+    let (address, _) = spawn_app_with_pool().await;
+    address
+}
+
+/// Spawn an application instance like `spawn_app`, also
+/// returning a pool for the test database so that tests
+/// can seed data that isn't writable through the API.
+pub async fn spawn_app_with_pool() -> (String, SqlitePool) {
     // Init values for configuration
     let mut config =
         config::load_config("../../synth.toml").expect("Failed to load configuration!");
@@ -28,6 +36,6 @@ pub async fn spawn_app() -> String {
         .expect("Failed to create the database pool!");
 
     // Run the application instance
-    let _ = tokio::spawn(webserver::run_webserver(listener, db_pool).unwrap());
-    format!("http://127.0.0.1:{}", port)
+    tokio::spawn(webserver::run_webserver(listener, db_pool.clone(), config.worker).unwrap());
+    (format!("http://127.0.0.1:{}", port), db_pool)
 }
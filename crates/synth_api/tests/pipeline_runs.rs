@@ -0,0 +1,223 @@
+mod helpers;
+
+use crate::helpers::{spawn_app, spawn_app_with_pool};
+use pretty_assertions::assert_eq;
+use reqwest::{Client, StatusCode};
+use synth_api::models::JSONResponse;
+use synth_common::{models, queries};
+
+#[tokio::test]
+async fn get_pipeline_run_success() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    let pipeline_run = models::PipelineRun {
+        id: "testpipelinerun".to_owned(),
+        pipeline_id: "pipeline1".to_owned(),
+        scheduled_time: "2023-12-06T09:00:00Z".to_owned(),
+        trigger: models::RunTrigger::Scheduled,
+        status: models::PipelineRunStatus::Queued,
+        created_at: "2023-12-06T09:00:01Z".to_owned(),
+        ..Default::default()
+    };
+    queries::insert_pipeline_run(&pipeline_run, &db_pool)
+        .await
+        .expect("Failed to insert pipeline run!");
+    queries::start_pipeline_run(&pipeline_run.id, "2023-12-06T09:00:02Z", &db_pool)
+        .await
+        .expect("Failed to start pipeline run!");
+
+    // Act
+    let url = &format!("{}/api/pipeline_runs/{}", server_address, pipeline_run.id);
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .expect("Failed to send request!");
+
+    // Assert that the run is returned with its start time
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: JSONResponse<models::PipelineRun> = response.json().await.unwrap();
+    assert_eq!(
+        body.data,
+        Some(vec![models::PipelineRun {
+            status: models::PipelineRunStatus::Running,
+            execution_start: Some("2023-12-06T09:00:02Z".to_owned()),
+            ..pipeline_run
+        }])
+    );
+}
+
+#[tokio::test]
+async fn get_pipeline_run_not_found() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let url = &format!("{}/api/pipeline_runs/doesnotexist", server_address);
+
+    // Act
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .expect("Failed to send request!");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::NOT_FOUND)
+}
+
+#[tokio::test]
+async fn cancel_pipeline_run_success() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    let pipeline_run = models::PipelineRun {
+        id: "cancelledpipelinerun".to_owned(),
+        pipeline_id: "pipeline1".to_owned(),
+        scheduled_time: "2023-12-13T09:00:00Z".to_owned(),
+        trigger: models::RunTrigger::Manual,
+        status: models::PipelineRunStatus::Queued,
+        ..Default::default()
+    };
+    queries::insert_pipeline_run(&pipeline_run, &db_pool)
+        .await
+        .expect("Failed to insert pipeline run!");
+    queries::start_pipeline_run(&pipeline_run.id, "2023-12-13T09:00:01Z", &db_pool)
+        .await
+        .expect("Failed to start pipeline run!");
+    let task_instance_id = "cancelledruntaskinstance".to_owned();
+    let queued_task_instance_id = "cancelledqueuedtaskinstance".to_owned();
+    for (id, task_id, status) in [
+        (&task_instance_id, "task1", models::TaskStatus::Running),
+        (
+            &queued_task_instance_id,
+            "task2",
+            models::TaskStatus::Queued,
+        ),
+    ] {
+        queries::insert_task_instance(
+            models::TaskInstance {
+                id: id.clone(),
+                task_id: task_id.to_owned(),
+                pipeline_id: "pipeline1".to_owned(),
+                pipeline_run_id: Some(pipeline_run.id.clone()),
+                status,
+                attempt: 1,
+                ..Default::default()
+            },
+            &db_pool,
+        )
+        .await
+        .expect("Failed to insert task instance!");
+    }
+
+    // Act
+    let url = &format!(
+        "{}/api/pipeline_runs/{}/cancel",
+        server_address, pipeline_run.id
+    );
+    let response = client
+        .post(url)
+        .send()
+        .await
+        .expect("Failed to POST cancel!");
+    let repeat_response = client
+        .post(url)
+        .send()
+        .await
+        .expect("Failed to POST cancel!");
+
+    // Assert that the run and its running TaskInstance were both cancelled
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: JSONResponse<models::PipelineRun> = response.json().await.unwrap();
+    assert_eq!(
+        body.data.unwrap()[0].status,
+        models::PipelineRunStatus::Cancelled
+    );
+    let task_instance = queries::select_task_instance_by_id(&task_instance_id, &db_pool)
+        .await
+        .unwrap();
+    assert_eq!(task_instance.status, models::TaskStatus::Cancelled);
+    // The running TaskInstance only ends once its executor stops it
+    assert_eq!(task_instance.execution_end, "");
+    let queued_task_instance =
+        queries::select_task_instance_by_id(&queued_task_instance_id, &db_pool)
+            .await
+            .unwrap();
+    assert_eq!(queued_task_instance.status, models::TaskStatus::Cancelled);
+    assert_ne!(queued_task_instance.execution_end, "");
+    assert_eq!(repeat_response.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn cancel_pipeline_run_partway_through() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    let pipeline_run = models::PipelineRun {
+        id: "partlycancelledpipelinerun".to_owned(),
+        pipeline_id: "pipeline1".to_owned(),
+        scheduled_time: "2024-01-08T09:00:00Z".to_owned(),
+        trigger: models::RunTrigger::Scheduled,
+        status: models::PipelineRunStatus::Queued,
+        ..Default::default()
+    };
+    queries::insert_pipeline_run(&pipeline_run, &db_pool)
+        .await
+        .expect("Failed to insert pipeline run!");
+    queries::start_pipeline_run(&pipeline_run.id, "2024-01-08T09:00:01Z", &db_pool)
+        .await
+        .expect("Failed to start pipeline run!");
+    // task1 has already finished, task2 hasn't been started yet
+    queries::insert_task_instance(
+        models::TaskInstance {
+            id: "partlycancelledtaskinstance".to_owned(),
+            task_id: "task1".to_owned(),
+            pipeline_id: "pipeline1".to_owned(),
+            pipeline_run_id: Some(pipeline_run.id.clone()),
+            execution_start: "2024-01-08T09:00:02Z".to_owned(),
+            execution_end: "2024-01-08T09:00:03Z".to_owned(),
+            status: models::TaskStatus::Success,
+            attempt: 1,
+            ..Default::default()
+        },
+        &db_pool,
+    )
+    .await
+    .expect("Failed to insert task instance!");
+
+    // Act
+    let url = &format!(
+        "{}/api/pipeline_runs/{}/cancel",
+        server_address, pipeline_run.id
+    );
+    let response = client
+        .post(url)
+        .send()
+        .await
+        .expect("Failed to POST cancel!");
+
+    // Assert that the finished Task is kept and the unstarted one is cancelled
+    assert_eq!(response.status(), StatusCode::OK);
+    let mut task_instances =
+        queries::select_task_instances_by_pipeline_run_id(&pipeline_run.id, &db_pool)
+            .await
+            .unwrap();
+    task_instances.sort_by(|a, b| a.task_id.cmp(&b.task_id));
+    let statuses: Vec<(&str, models::TaskStatus)> = task_instances
+        .iter()
+        .map(|task_instance| (task_instance.task_id.as_str(), task_instance.status))
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            ("task1", models::TaskStatus::Success),
+            ("task2", models::TaskStatus::Cancelled),
+        ]
+    );
+    assert_eq!(
+        task_instances[1].scheduled_time,
+        pipeline_run.scheduled_time
+    );
+    assert_ne!(task_instances[1].execution_end, "");
+}
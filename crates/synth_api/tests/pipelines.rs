@@ -1,10 +1,11 @@
 mod helpers;
 
-use crate::helpers::spawn_app;
+use crate::helpers::{spawn_app, spawn_app_with_pool};
 use pretty_assertions::assert_eq;
 use reqwest::{Client, StatusCode};
+use std::collections::HashMap;
 use synth_api::models::JSONResponse;
-use synth_common::models;
+use synth_common::{models, queries};
 
 #[tokio::test]
 async fn list_pipelines_success() {
@@ -21,7 +22,10 @@ async fn list_pipelines_success() {
         .expect("Failed to send request!");
 
     // Assert
-    assert_eq!(response.status(), StatusCode::OK)
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: JSONResponse<models::Pipeline> = response.json().await.unwrap();
+    let pipelines = body.data.expect("No pipelines returned!");
+    assert!(pipelines.iter().any(|pipeline| pipeline.id == "pipeline1"));
 }
 
 #[tokio::test]
@@ -36,6 +40,9 @@ async fn create_one_pipeline_success() {
     let create_data = models::Pipeline {
         id: id.clone(),
         schedule: "1 * * * *".to_owned(),
+        max_active_runs: Some(1),
+        overlap_policy: models::OverlapPolicy::CancelPrevious,
+        ..Default::default()
     };
     let create_response = client
         .post(create_url)
@@ -102,3 +109,667 @@ async fn create_pipeline_failures() {
         );
     }
 }
+
+#[tokio::test]
+async fn create_pipeline_without_active_runs_fails() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let url = &format!("{}/api/pipelines", server_address);
+    let pipeline = models::Pipeline {
+        id: "testpipeline".to_owned(),
+        schedule: "1 * * * *".to_owned(),
+        max_active_runs: Some(0),
+        ..Default::default()
+    };
+
+    // Act
+    let response = client
+        .post(url)
+        .json(&pipeline)
+        .send()
+        .await
+        .expect("Failed to send request!");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn backfill_pipeline_success() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let id = "backfillpipeline".to_string();
+    let pipeline = models::Pipeline {
+        id: id.clone(),
+        schedule: "0 * * * *".to_owned(),
+        ..Default::default()
+    };
+    let create_url = &format!("{}/api/pipelines", server_address);
+    client
+        .post(create_url)
+        .json(&pipeline)
+        .send()
+        .await
+        .expect("Failed to POST pipeline!");
+
+    // Act
+    let url = &format!("{}/api/pipelines/{}/backfill", server_address, id);
+    let backfill = models::Backfill {
+        start: "2023-11-20T00:00:00Z".to_owned(),
+        end: "2023-11-20T03:00:00Z".to_owned(),
+    };
+    let response = client
+        .post(url)
+        .json(&backfill)
+        .send()
+        .await
+        .expect("Failed to POST backfill!");
+    let repeat_response = client
+        .post(url)
+        .json(&backfill)
+        .send()
+        .await
+        .expect("Failed to POST backfill!");
+
+    // Assert that every hourly tick was queued exactly once
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body: JSONResponse<models::PipelineRun> = response.json().await.unwrap();
+    let scheduled_times: Vec<String> = body
+        .data
+        .unwrap()
+        .into_iter()
+        .map(|pipeline_run| pipeline_run.scheduled_time)
+        .collect();
+    assert_eq!(
+        scheduled_times,
+        vec![
+            "2023-11-20T00:00:00Z",
+            "2023-11-20T01:00:00Z",
+            "2023-11-20T02:00:00Z",
+            "2023-11-20T03:00:00Z",
+        ]
+    );
+    let repeat_body: JSONResponse<models::PipelineRun> = repeat_response.json().await.unwrap();
+    assert_eq!(repeat_body.data, Some(vec![]));
+}
+
+#[tokio::test]
+async fn create_pipeline_with_unknown_timezone_fails() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let url = &format!("{}/api/pipelines", server_address);
+    let pipeline = models::Pipeline {
+        id: "testpipeline".to_owned(),
+        schedule: "1 * * * *".to_owned(),
+        timezone: Some("Europe/Atlantis".to_owned()),
+        ..Default::default()
+    };
+
+    // Act
+    let response = client
+        .post(url)
+        .json(&pipeline)
+        .send()
+        .await
+        .expect("Failed to send request!");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+/// Create a Pipeline and backfill it, returning the scheduled times of its runs
+async fn backfill_scheduled_times(
+    pipeline: models::Pipeline,
+    backfill: models::Backfill,
+) -> Vec<String> {
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let create_url = &format!("{}/api/pipelines", server_address);
+    client
+        .post(create_url)
+        .json(&pipeline)
+        .send()
+        .await
+        .expect("Failed to POST pipeline!");
+    let url = &format!("{}/api/pipelines/{}/backfill", server_address, pipeline.id);
+    let response = client
+        .post(url)
+        .json(&backfill)
+        .send()
+        .await
+        .expect("Failed to POST backfill!");
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body: JSONResponse<models::PipelineRun> = response.json().await.unwrap();
+    body.data
+        .unwrap()
+        .into_iter()
+        .map(|pipeline_run| pipeline_run.scheduled_time)
+        .collect()
+}
+
+#[tokio::test]
+async fn backfill_pipeline_in_timezone_fires_after_skipped_time() {
+    // Arrange a daily 02:30 run in Berlin, where 02:30 is skipped on 2024-03-31
+    let pipeline = models::Pipeline {
+        id: "berlinpipeline".to_owned(),
+        schedule: "30 2 * * *".to_owned(),
+        timezone: Some("Europe/Berlin".to_owned()),
+        ..Default::default()
+    };
+    let backfill = models::Backfill {
+        start: "2024-03-30T00:00:00Z".to_owned(),
+        end: "2024-04-01T12:00:00Z".to_owned(),
+    };
+
+    // Act
+    let scheduled_times = backfill_scheduled_times(pipeline, backfill).await;
+
+    // Assert that the skipped run happens once the clocks have gone forward
+    assert_eq!(
+        scheduled_times,
+        vec![
+            "2024-03-30T01:30:00Z",
+            "2024-03-31T01:00:00Z",
+            "2024-04-01T00:30:00Z",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn backfill_pipeline_in_timezone_fires_repeated_time_once() {
+    // Arrange an hourly run in Berlin, where 02:00 happens twice on 2024-10-27
+    let pipeline = models::Pipeline {
+        id: "berlinpipeline".to_owned(),
+        schedule: "0 * * * *".to_owned(),
+        timezone: Some("Europe/Berlin".to_owned()),
+        ..Default::default()
+    };
+    let backfill = models::Backfill {
+        start: "2024-10-26T23:00:00Z".to_owned(),
+        end: "2024-10-27T02:00:00Z".to_owned(),
+    };
+
+    // Act
+    let scheduled_times = backfill_scheduled_times(pipeline, backfill).await;
+
+    // Assert that 02:00 only runs the first time around
+    assert_eq!(
+        scheduled_times,
+        vec![
+            "2024-10-26T23:00:00Z",
+            "2024-10-27T00:00:00Z",
+            "2024-10-27T02:00:00Z",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn create_pipeline_with_invalid_schedule_fails() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let url = &format!("{}/api/pipelines", server_address);
+    let invalid_pipelines = vec![
+        ("@fortnightly", None, None),
+        ("@every 15x", None, None),
+        ("* * * *", None, None),
+        ("0 0 * * * * *", None, None),
+        ("@daily", Some("yesterday"), None),
+        ("@daily", Some("2024-01-02"), Some("2024-01-01")),
+    ];
+
+    for (schedule, start_date, end_date) in invalid_pipelines {
+        let pipeline = models::Pipeline {
+            id: "testpipeline".to_owned(),
+            schedule: schedule.to_owned(),
+            start_date: start_date.map(str::to_owned),
+            end_date: end_date.map(str::to_owned),
+            ..Default::default()
+        };
+
+        // Act
+        let response = client
+            .post(url)
+            .json(&pipeline)
+            .send()
+            .await
+            .expect("Failed to send request!");
+
+        // Assert
+        assert_eq!(
+            response.status(),
+            StatusCode::BAD_REQUEST,
+            "Schedule '{}' was accepted!",
+            schedule
+        );
+    }
+}
+
+#[tokio::test]
+async fn backfill_pipeline_with_interval_counts_from_start_date() {
+    // Arrange
+    let pipeline = models::Pipeline {
+        id: "intervalpipeline".to_owned(),
+        schedule: "@every 15m".to_owned(),
+        start_date: Some("2024-01-01T00:05:00Z".to_owned()),
+        ..Default::default()
+    };
+    let backfill = models::Backfill {
+        start: "2024-01-01T00:00:00Z".to_owned(),
+        end: "2024-01-01T00:40:00Z".to_owned(),
+    };
+
+    // Act
+    let scheduled_times = backfill_scheduled_times(pipeline, backfill).await;
+
+    // Assert
+    assert_eq!(
+        scheduled_times,
+        vec![
+            "2024-01-01T00:05:00Z",
+            "2024-01-01T00:20:00Z",
+            "2024-01-01T00:35:00Z",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn backfill_pipeline_with_seconds_fires_within_minute() {
+    // Arrange
+    let pipeline = models::Pipeline {
+        id: "secondspipeline".to_owned(),
+        schedule: "*/20 0 9 * * *".to_owned(),
+        ..Default::default()
+    };
+    let backfill = models::Backfill {
+        start: "2024-01-01T09:00:00Z".to_owned(),
+        end: "2024-01-01T09:01:00Z".to_owned(),
+    };
+
+    // Act
+    let scheduled_times = backfill_scheduled_times(pipeline, backfill).await;
+
+    // Assert
+    assert_eq!(
+        scheduled_times,
+        vec![
+            "2024-01-01T09:00:00Z",
+            "2024-01-01T09:00:20Z",
+            "2024-01-01T09:00:40Z",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn backfill_pipeline_with_preset_stops_at_end_date() {
+    // Arrange
+    let pipeline = models::Pipeline {
+        id: "presetpipeline".to_owned(),
+        schedule: "@daily".to_owned(),
+        end_date: Some("2024-01-02".to_owned()),
+        ..Default::default()
+    };
+    let backfill = models::Backfill {
+        start: "2024-01-01T00:00:00Z".to_owned(),
+        end: "2024-01-05T00:00:00Z".to_owned(),
+    };
+
+    // Act
+    let scheduled_times = backfill_scheduled_times(pipeline, backfill).await;
+
+    // Assert that the run on the end date is included, but none after it
+    assert_eq!(
+        scheduled_times,
+        vec!["2024-01-01T00:00:00Z", "2024-01-02T00:00:00Z"]
+    );
+}
+
+#[tokio::test]
+async fn backfill_pipeline_failures() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let request_data = vec![
+        // Unknown Pipeline
+        (
+            "missingpipeline",
+            "2023-11-20T00:00:00Z",
+            "2023-11-20T03:00:00Z",
+            StatusCode::NOT_FOUND,
+        ),
+        // Start is after the end
+        (
+            "pipeline1",
+            "2023-11-20T03:00:00Z",
+            "2023-11-20T00:00:00Z",
+            StatusCode::BAD_REQUEST,
+        ),
+        // Invalid timestamp
+        (
+            "pipeline1",
+            "yesterday",
+            "2023-11-20T00:00:00Z",
+            StatusCode::BAD_REQUEST,
+        ),
+    ];
+
+    for (id, start, end, status) in request_data {
+        // Act
+        let url = &format!("{}/api/pipelines/{}/backfill", server_address, id);
+        let backfill = models::Backfill {
+            start: start.to_owned(),
+            end: end.to_owned(),
+        };
+        let response = client
+            .post(url)
+            .json(&backfill)
+            .send()
+            .await
+            .expect("Failed to send request!");
+
+        // Assert
+        assert_eq!(
+            response.status(),
+            status,
+            "Unexpected status for backfill of '{}' from {} to {}",
+            id,
+            start,
+            end
+        );
+    }
+}
+
+#[tokio::test]
+async fn list_pipeline_runs_success() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let id = "runspipeline".to_string();
+    let pipeline = models::Pipeline {
+        id: id.clone(),
+        schedule: "0 * * * *".to_owned(),
+        ..Default::default()
+    };
+    let create_url = &format!("{}/api/pipelines", server_address);
+    client
+        .post(create_url)
+        .json(&pipeline)
+        .send()
+        .await
+        .expect("Failed to POST pipeline!");
+    let backfill_url = &format!("{}/api/pipelines/{}/backfill", server_address, id);
+    let backfill = models::Backfill {
+        start: "2023-11-20T00:00:00Z".to_owned(),
+        end: "2023-11-20T01:00:00Z".to_owned(),
+    };
+    client
+        .post(backfill_url)
+        .json(&backfill)
+        .send()
+        .await
+        .expect("Failed to POST backfill!");
+
+    // Act
+    let url = &format!("{}/api/pipelines/{}/runs", server_address, id);
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .expect("Failed to send request!");
+
+    // Assert that the runs come back most recent first
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: JSONResponse<models::PipelineRun> = response.json().await.unwrap();
+    let scheduled_times: Vec<String> = body
+        .data
+        .unwrap()
+        .into_iter()
+        .map(|pipeline_run| pipeline_run.scheduled_time)
+        .collect();
+    assert_eq!(
+        scheduled_times,
+        vec!["2023-11-20T01:00:00Z", "2023-11-20T00:00:00Z"]
+    );
+}
+
+#[tokio::test]
+async fn list_pipeline_runs_unknown_pipeline_fails() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let url = &format!("{}/api/pipelines/doesnotexist/runs", server_address);
+
+    // Act
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .expect("Failed to send request!");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::NOT_FOUND)
+}
+
+#[tokio::test]
+async fn trigger_pipeline_success() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let id = "triggerpipeline".to_string();
+    let pipeline = models::Pipeline {
+        id: id.clone(),
+        schedule: "0 * * * *".to_owned(),
+        ..Default::default()
+    };
+    let create_url = &format!("{}/api/pipelines", server_address);
+    client
+        .post(create_url)
+        .json(&pipeline)
+        .send()
+        .await
+        .expect("Failed to POST pipeline!");
+    let trigger = models::Trigger {
+        logical_date: Some("2023-12-08T09:30:00Z".to_owned()),
+        params: HashMap::from([("TARGET".to_owned(), "staging".to_owned())]),
+    };
+
+    // Act
+    let url = &format!("{}/api/pipelines/{}/trigger", server_address, id);
+    let response = client
+        .post(url)
+        .json(&trigger)
+        .send()
+        .await
+        .expect("Failed to POST trigger!");
+    let repeat_response = client
+        .post(url)
+        .json(&trigger)
+        .send()
+        .await
+        .expect("Failed to POST trigger!");
+
+    // Assert that a queued manual run was created only once for the logical date
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body: JSONResponse<models::PipelineRun> = response.json().await.unwrap();
+    let pipeline_run = &body.data.unwrap()[0];
+    assert_eq!(pipeline_run.trigger, models::RunTrigger::Manual);
+    assert_eq!(pipeline_run.status, models::PipelineRunStatus::Queued);
+    assert_eq!(pipeline_run.scheduled_time, "2023-12-08T09:30:00Z");
+    assert_eq!(pipeline_run.params.0, trigger.params);
+    assert_eq!(repeat_response.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn trigger_pipeline_keeps_scheduled_tick() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    let id = "tickpipeline".to_string();
+    let pipeline = models::Pipeline {
+        id: id.clone(),
+        schedule: "0 * * * *".to_owned(),
+        ..Default::default()
+    };
+    let create_url = &format!("{}/api/pipelines", server_address);
+    client
+        .post(create_url)
+        .json(&pipeline)
+        .send()
+        .await
+        .expect("Failed to POST pipeline!");
+    let trigger = models::Trigger {
+        logical_date: Some("2023-12-08T10:00:00Z".to_owned()),
+        ..Default::default()
+    };
+
+    // Act
+    let url = &format!("{}/api/pipelines/{}/trigger", server_address, id);
+    let response = client
+        .post(url)
+        .json(&trigger)
+        .send()
+        .await
+        .expect("Failed to POST trigger!");
+    let scheduled_run = models::PipelineRun {
+        id: "tickpipelinescheduledrun".to_owned(),
+        pipeline_id: id.clone(),
+        scheduled_time: "2023-12-08T10:00:00Z".to_owned(),
+        trigger: models::RunTrigger::Scheduled,
+        status: models::PipelineRunStatus::Queued,
+        ..Default::default()
+    };
+    let inserted = queries::insert_pipeline_run(&scheduled_run, &db_pool)
+        .await
+        .expect("Failed to insert pipeline run!");
+
+    // Assert that the manual run didn't take the place of the scheduled tick
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert!(inserted);
+    let pipeline_runs = queries::select_pipeline_runs_by_pipeline_id(&id, &db_pool)
+        .await
+        .unwrap();
+    assert_eq!(pipeline_runs.len(), 2);
+}
+
+#[tokio::test]
+async fn trigger_pipeline_failures() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let test_cases = [
+        (
+            "doesnotexist",
+            models::Trigger::default(),
+            StatusCode::NOT_FOUND,
+        ),
+        (
+            "pipeline1",
+            models::Trigger {
+                logical_date: Some("yesterday".to_owned()),
+                ..Default::default()
+            },
+            StatusCode::BAD_REQUEST,
+        ),
+        (
+            "pipeline1",
+            models::Trigger {
+                params: HashMap::from([("BAD-NAME".to_owned(), "value".to_owned())]),
+                ..Default::default()
+            },
+            StatusCode::BAD_REQUEST,
+        ),
+    ];
+
+    for (id, trigger, expected_status) in test_cases {
+        // Act
+        let url = &format!("{}/api/pipelines/{}/trigger", server_address, id);
+        let response = client
+            .post(url)
+            .json(&trigger)
+            .send()
+            .await
+            .expect("Failed to POST trigger!");
+
+        // Assert
+        assert_eq!(response.status(), expected_status);
+    }
+}
+
+#[tokio::test]
+async fn pause_and_resume_pipeline_success() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let id = "pausedpipeline".to_string();
+    let pipeline = models::Pipeline {
+        id: id.clone(),
+        schedule: "0 * * * *".to_owned(),
+        is_paused: true,
+        ..Default::default()
+    };
+    let create_url = &format!("{}/api/pipelines", server_address);
+    client
+        .post(create_url)
+        .json(&pipeline)
+        .send()
+        .await
+        .expect("Failed to POST pipeline!");
+
+    // Act
+    let resume_url = &format!("{}/api/pipelines/{}/resume", server_address, id);
+    let resume_response = client
+        .post(resume_url)
+        .send()
+        .await
+        .expect("Failed to POST resume!");
+    // Re-registering must not undo the resume
+    client
+        .post(create_url)
+        .json(&pipeline)
+        .send()
+        .await
+        .expect("Failed to POST pipeline!");
+    let get_url = &format!("{}/api/pipelines/{}", server_address, id);
+    let get_response = client
+        .get(get_url)
+        .send()
+        .await
+        .expect("Failed to send request!");
+    let pause_url = &format!("{}/api/pipelines/{}/pause", server_address, id);
+    let pause_response = client
+        .post(pause_url)
+        .send()
+        .await
+        .expect("Failed to POST pause!");
+
+    // Assert
+    assert_eq!(resume_response.status(), StatusCode::OK);
+    let resume_body: JSONResponse<models::Pipeline> = resume_response.json().await.unwrap();
+    assert!(!resume_body.data.unwrap()[0].is_paused);
+    let get_body: JSONResponse<models::Pipeline> = get_response.json().await.unwrap();
+    assert!(!get_body.data.unwrap()[0].is_paused);
+    assert_eq!(pause_response.status(), StatusCode::OK);
+    let pause_body: JSONResponse<models::Pipeline> = pause_response.json().await.unwrap();
+    assert!(pause_body.data.unwrap()[0].is_paused);
+}
+
+#[tokio::test]
+async fn pause_unknown_pipeline_fails() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let url = &format!("{}/api/pipelines/doesnotexist/pause", server_address);
+
+    // Act
+    let response = client
+        .post(url)
+        .send()
+        .await
+        .expect("Failed to POST pause!");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::NOT_FOUND)
+}
@@ -0,0 +1,269 @@
+mod helpers;
+
+use crate::helpers::{spawn_app, spawn_app_with_pool};
+use pretty_assertions::assert_eq;
+use reqwest::{Client, StatusCode};
+use sqlx::SqlitePool;
+use synth_api::models::JSONResponse;
+use synth_common::{models, queries};
+
+/// Create a pool through the API
+async fn create_pool(server_address: &str, client: &Client, id: &str, slots: i64) -> StatusCode {
+    client
+        .post(format!("{}/api/pools", server_address))
+        .json(&models::ResourcePool {
+            id: id.to_owned(),
+            slots,
+            ..Default::default()
+        })
+        .send()
+        .await
+        .expect("Failed to POST pool!")
+        .status()
+}
+
+/// Queue a TaskInstance of a Task in a running PipelineRun
+async fn queue_task_instance(task_id: &str, db_pool: &SqlitePool) {
+    queries::insert_task_instance(
+        models::TaskInstance {
+            id: format!("{}instance", task_id),
+            task_id: task_id.to_owned(),
+            pipeline_id: "poolpipeline".to_owned(),
+            pipeline_run_id: Some("poolpipelinerun".to_owned()),
+            status: models::TaskStatus::Queued,
+            created_at: format!("2023-12-27 09:00:00 {}", task_id),
+            attempt: 1,
+            last_attempt: 1,
+            ..Default::default()
+        },
+        db_pool,
+    )
+    .await
+    .expect("Failed to insert task instance!");
+}
+
+#[tokio::test]
+async fn create_and_list_pools_success() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+
+    // Act
+    let create_status = create_pool(&server_address, &client, "legacy_db", 2).await;
+    let response = client
+        .get(format!("{}/api/pools", server_address))
+        .send()
+        .await
+        .expect("Failed to GET pools!");
+
+    // Assert
+    assert_eq!(create_status, StatusCode::CREATED);
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: JSONResponse<models::ResourcePool> = response.json().await.unwrap();
+    assert_eq!(
+        body.data.unwrap(),
+        vec![models::ResourcePool {
+            id: "legacy_db".to_owned(),
+            slots: 2,
+            used_slots: 0,
+        }]
+    );
+}
+
+#[tokio::test]
+async fn create_pool_without_slots_fails() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+
+    // Act
+    let create_status = create_pool(&server_address, &client, "legacy_db", 0).await;
+
+    // Assert
+    assert_eq!(create_status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn create_task_in_unknown_or_small_pool_fails() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    create_pool(&server_address, &client, "legacy_db", 2).await;
+    let tasks = vec![
+        models::Task {
+            id: "unknownpooltask".to_owned(),
+            pipeline_id: "poolpipeline".to_owned(),
+            command: "echo unknown".to_owned(),
+            pool: Some("missing_pool".to_owned()),
+            ..Default::default()
+        },
+        models::Task {
+            id: "bigtask".to_owned(),
+            pipeline_id: "poolpipeline".to_owned(),
+            command: "echo big".to_owned(),
+            pool: Some("legacy_db".to_owned()),
+            pool_slots: 3,
+            ..Default::default()
+        },
+    ];
+
+    for task in tasks {
+        // Act
+        let response = client
+            .post(format!("{}/api/tasks", server_address))
+            .json(&task)
+            .send()
+            .await
+            .expect("Failed to POST task!");
+
+        // Assert
+        assert_eq!(
+            response.status(),
+            StatusCode::BAD_REQUEST,
+            "Didn't get a 400 for Task '{}'",
+            task.id
+        );
+    }
+}
+
+#[tokio::test]
+async fn claim_task_instance_respects_pool_slots() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    create_pool(&server_address, &client, "legacy_db", 2).await;
+    for (task_id, pool_slots) in [("bigtask", 2), ("smalltask", 1)] {
+        let task = models::Task {
+            id: task_id.to_owned(),
+            pipeline_id: "poolpipeline".to_owned(),
+            command: "echo pooled".to_owned(),
+            pool: Some("legacy_db".to_owned()),
+            pool_slots,
+            ..Default::default()
+        };
+        queries::upsert_task(&task, &db_pool).await.unwrap();
+    }
+    queue_task_instance("bigtask", &db_pool).await;
+    queue_task_instance("smalltask", &db_pool).await;
+    let claim = || {
+        queries::claim_task_instance(
+            "executor",
+            "2023-12-27T09:00:00Z",
+            "9999-01-01T00:00:00Z",
+            &db_pool,
+        )
+    };
+
+    // Act
+    let first_claim = claim().await.unwrap();
+    let second_claim = claim().await.unwrap();
+    let pool = queries::select_pool_by_id("legacy_db", &db_pool)
+        .await
+        .unwrap();
+
+    // Assert that the small Task waits until the big one frees its slots
+    assert_eq!(first_claim.unwrap().task_id, "bigtask");
+    assert_eq!(second_claim, None);
+    assert_eq!(pool.used_slots, 2);
+}
+
+#[tokio::test]
+async fn shrink_pool_below_its_tasks_fails() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    create_pool(&server_address, &client, "legacy_db", 2).await;
+    let task = models::Task {
+        id: "bigtask".to_owned(),
+        pipeline_id: "poolpipeline".to_owned(),
+        command: "echo big".to_owned(),
+        pool: Some("legacy_db".to_owned()),
+        pool_slots: 2,
+        ..Default::default()
+    };
+    queries::upsert_task(&task, &db_pool).await.unwrap();
+
+    // Act
+    let create_status = create_pool(&server_address, &client, "legacy_db", 1).await;
+    let pool = queries::select_pool_by_id("legacy_db", &db_pool)
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(create_status, StatusCode::CONFLICT);
+    assert_eq!(pool.slots, 2);
+}
+
+#[tokio::test]
+async fn shrink_pool_below_its_running_tasks_fails() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    create_pool(&server_address, &client, "legacy_db", 2).await;
+    for task_id in ["firsttask", "secondtask"] {
+        let task = models::Task {
+            id: task_id.to_owned(),
+            pipeline_id: "poolpipeline".to_owned(),
+            command: "echo pooled".to_owned(),
+            pool: Some("legacy_db".to_owned()),
+            ..Default::default()
+        };
+        queries::upsert_task(&task, &db_pool).await.unwrap();
+        queue_task_instance(task_id, &db_pool).await;
+        queries::claim_task_instance(
+            "executor",
+            "2023-12-27T09:00:00Z",
+            "9999-01-01T00:00:00Z",
+            &db_pool,
+        )
+        .await
+        .unwrap()
+        .expect("Failed to claim task instance!");
+    }
+
+    // Act
+    let create_status = create_pool(&server_address, &client, "legacy_db", 1).await;
+    let pool = queries::select_pool_by_id("legacy_db", &db_pool)
+        .await
+        .unwrap();
+
+    // Assert that the pool keeps enough slots for both running Tasks
+    assert_eq!(create_status, StatusCode::CONFLICT);
+    assert_eq!(pool.slots, 2);
+    assert_eq!(pool.used_slots, 2);
+}
+
+#[tokio::test]
+async fn task_instance_that_never_fits_its_pool_fails() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    create_pool(&server_address, &client, "legacy_db", 2).await;
+    for (task_id, pool) in [
+        ("unknownpooltask", "missing_pool"),
+        ("pooltask", "legacy_db"),
+    ] {
+        let task = models::Task {
+            id: task_id.to_owned(),
+            pipeline_id: "poolpipeline".to_owned(),
+            command: "echo pooled".to_owned(),
+            pool: Some(pool.to_owned()),
+            ..Default::default()
+        };
+        queries::upsert_task(&task, &db_pool).await.unwrap();
+    }
+    queue_task_instance("unknownpooltask", &db_pool).await;
+    queue_task_instance("pooltask", &db_pool).await;
+
+    // Act
+    let failed = queries::fail_unclaimable_task_instances("2023-12-27T09:00:00Z", &db_pool)
+        .await
+        .unwrap();
+
+    // Assert that only the TaskInstance without a pool fails
+    assert_eq!(failed, vec!["unknownpooltaskinstance".to_owned()]);
+    let task_instance = queries::select_task_instance_by_id("pooltaskinstance", &db_pool)
+        .await
+        .unwrap();
+    assert_eq!(task_instance.status, models::TaskStatus::Queued);
+}
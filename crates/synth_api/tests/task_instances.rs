@@ -0,0 +1,554 @@
+mod helpers;
+
+use crate::helpers::{spawn_app, spawn_app_with_pool};
+use pretty_assertions::assert_eq;
+use reqwest::{Client, StatusCode};
+use synth_api::models::JSONResponse;
+use synth_common::{models, queries};
+
+#[tokio::test]
+async fn list_task_instances_success() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let url = &format!("{}/api/task_instances", server_address);
+
+    // Act
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .expect("Failed to send request!");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::OK)
+}
+
+#[tokio::test]
+async fn list_task_instances_by_status_success() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    for (id, status, exit_code) in [
+        (
+            "succeededtaskinstance",
+            models::TaskStatus::Success,
+            Some(0),
+        ),
+        ("failedtaskinstance", models::TaskStatus::Failed, Some(1)),
+    ] {
+        queries::insert_task_instance(
+            models::TaskInstance {
+                id: id.to_owned(),
+                task_id: "task1".to_owned(),
+                pipeline_id: "pipeline1".to_owned(),
+                status,
+                exit_code,
+                attempt: 1,
+                ..Default::default()
+            },
+            &db_pool,
+        )
+        .await
+        .expect("Failed to insert task instance!");
+    }
+
+    // Act
+    let url = &format!("{}/api/task_instances?status=failed", server_address);
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .expect("Failed to send request!");
+
+    // Assert that only the failed TaskInstance is returned, with its exit code
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: JSONResponse<models::TaskInstance> = response.json().await.unwrap();
+    let task_instances = body.data.unwrap();
+    assert_eq!(task_instances.len(), 1);
+    assert_eq!(task_instances[0].id, "failedtaskinstance");
+    assert_eq!(task_instances[0].status, models::TaskStatus::Failed);
+    assert_eq!(task_instances[0].exit_code, Some(1));
+}
+
+#[tokio::test]
+async fn list_task_instances_by_unknown_status_fails() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let url = &format!("{}/api/task_instances?status=exploded", server_address);
+
+    // Act
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .expect("Failed to send request!");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST)
+}
+
+#[tokio::test]
+async fn get_task_instance_logs_success() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    let task_instance_id = "testtaskinstance".to_owned();
+    let task_logs = vec![
+        models::TaskLog {
+            task_instance_id: task_instance_id.clone(),
+            timestamp: "2023-12-01T09:00:00Z".to_owned(),
+            stream: "stdout".to_owned(),
+            line: "starting".to_owned(),
+            ..Default::default()
+        },
+        models::TaskLog {
+            task_instance_id: task_instance_id.clone(),
+            timestamp: "2023-12-01T09:00:01Z".to_owned(),
+            stream: "stderr".to_owned(),
+            line: "something went wrong".to_owned(),
+            ..Default::default()
+        },
+    ];
+    queries::insert_task_logs(&task_logs, &db_pool)
+        .await
+        .expect("Failed to insert task logs!");
+
+    // Act
+    let url = &format!(
+        "{}/api/task_instances/{}/logs",
+        server_address, task_instance_id
+    );
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .expect("Failed to send request!");
+
+    // Assert that the lines come back in order with their streams
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: JSONResponse<models::TaskLog> = response.json().await.unwrap();
+    let lines: Vec<String> = body
+        .data
+        .unwrap()
+        .iter()
+        .map(|task_log| task_log.to_string())
+        .collect();
+    assert_eq!(
+        lines,
+        vec![
+            "2023-12-01T09:00:00Z [stdout] starting",
+            "2023-12-01T09:00:01Z [stderr] something went wrong",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn stream_task_instance_logs_success() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    let task_instance_id = "streamedtaskinstance".to_owned();
+    queries::insert_task_instance(
+        models::TaskInstance {
+            id: task_instance_id.clone(),
+            task_id: "task1".to_owned(),
+            pipeline_id: "pipeline1".to_owned(),
+            status: models::TaskStatus::Success,
+            exit_code: Some(0),
+            attempt: 1,
+            ..Default::default()
+        },
+        &db_pool,
+    )
+    .await
+    .expect("Failed to insert task instance!");
+    let task_logs = vec![
+        models::TaskLog {
+            task_instance_id: task_instance_id.clone(),
+            timestamp: "2023-12-01T09:00:00Z".to_owned(),
+            stream: "stdout".to_owned(),
+            line: "first".to_owned(),
+            ..Default::default()
+        },
+        models::TaskLog {
+            task_instance_id: task_instance_id.clone(),
+            timestamp: "2023-12-01T09:00:01Z".to_owned(),
+            stream: "stdout".to_owned(),
+            line: "second".to_owned(),
+            ..Default::default()
+        },
+    ];
+    queries::insert_task_logs(&task_logs, &db_pool)
+        .await
+        .expect("Failed to insert task logs!");
+
+    // Act
+    let url = &format!(
+        "{}/api/task_instances/{}/logs/stream",
+        server_address, task_instance_id
+    );
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .expect("Failed to send request!");
+
+    // Assert that every line is sent before the stream ends with the status
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()["content-type"].to_str().unwrap(),
+        "text/event-stream"
+    );
+    let body = response.text().await.unwrap();
+    let first = body
+        .find("\"line\":\"first\"")
+        .expect("Missing first line!");
+    let second = body
+        .find("\"line\":\"second\"")
+        .expect("Missing second line!");
+    let end = body
+        .find("event: end\ndata: success")
+        .expect("Missing end!");
+    assert!(first < second && second < end);
+}
+
+#[tokio::test]
+async fn stream_task_instance_logs_not_found() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let url = &format!(
+        "{}/api/task_instances/missingtaskinstance/logs/stream",
+        server_address
+    );
+
+    // Act
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .expect("Failed to send request!");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::NOT_FOUND)
+}
+
+#[tokio::test]
+async fn cancel_task_instance_success() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    let task_instance_id = "cancelledtaskinstance".to_owned();
+    queries::insert_task_instance(
+        models::TaskInstance {
+            id: task_instance_id.clone(),
+            task_id: "task1".to_owned(),
+            pipeline_id: "pipeline1".to_owned(),
+            status: models::TaskStatus::Running,
+            attempt: 1,
+            ..Default::default()
+        },
+        &db_pool,
+    )
+    .await
+    .expect("Failed to insert task instance!");
+
+    // Act
+    let url = &format!(
+        "{}/api/task_instances/{}/cancel",
+        server_address, task_instance_id
+    );
+    let response = client
+        .post(url)
+        .send()
+        .await
+        .expect("Failed to POST cancel!");
+    let repeat_response = client
+        .post(url)
+        .send()
+        .await
+        .expect("Failed to POST cancel!");
+
+    // Assert that only the running TaskInstance could be cancelled
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: JSONResponse<models::TaskInstance> = response.json().await.unwrap();
+    assert_eq!(body.data.unwrap()[0].status, models::TaskStatus::Cancelled);
+    assert_eq!(repeat_response.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn cancel_queued_task_instance_ends_it() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    for (id, status) in [
+        ("queuedtaskinstance", models::TaskStatus::Queued),
+        ("succeededtaskinstance", models::TaskStatus::Success),
+    ] {
+        queries::insert_task_instance(
+            models::TaskInstance {
+                id: id.to_owned(),
+                task_id: "task1".to_owned(),
+                pipeline_id: "pipeline1".to_owned(),
+                status,
+                attempt: 1,
+                ..Default::default()
+            },
+            &db_pool,
+        )
+        .await
+        .expect("Failed to insert task instance!");
+    }
+
+    // Act
+    let queued_response = client
+        .post(format!(
+            "{}/api/task_instances/queuedtaskinstance/cancel",
+            server_address
+        ))
+        .send()
+        .await
+        .expect("Failed to POST cancel!");
+    let succeeded_response = client
+        .post(format!(
+            "{}/api/task_instances/succeededtaskinstance/cancel",
+            server_address
+        ))
+        .send()
+        .await
+        .expect("Failed to POST cancel!");
+
+    // Assert that the queued TaskInstance ended without waiting on a process
+    assert_eq!(queued_response.status(), StatusCode::OK);
+    let body: JSONResponse<models::TaskInstance> = queued_response.json().await.unwrap();
+    assert_ne!(body.data.unwrap()[0].execution_end, "");
+    let unfinished = queries::select_unfinished_cancelled_task_instance_ids(&db_pool)
+        .await
+        .unwrap();
+    assert!(unfinished.is_empty());
+    assert_eq!(succeeded_response.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn cancel_task_instance_not_found() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let url = &format!(
+        "{}/api/task_instances/missingtaskinstance/cancel",
+        server_address
+    );
+
+    // Act
+    let response = client
+        .post(url)
+        .send()
+        .await
+        .expect("Failed to POST cancel!");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::NOT_FOUND)
+}
+
+/// Insert a PipelineRun with a single TaskInstance of `task1` in it
+async fn insert_pipeline_run_with_task_instance(
+    pipeline_run_status: models::PipelineRunStatus,
+    task_instance_status: models::TaskStatus,
+    db_pool: &sqlx::SqlitePool,
+) -> String {
+    let pipeline_run = models::PipelineRun {
+        id: format!("{}pipelinerun", pipeline_run_status),
+        pipeline_id: "pipeline1".to_owned(),
+        scheduled_time: "2023-12-15T09:00:00Z".to_owned(),
+        trigger: models::RunTrigger::Manual,
+        status: pipeline_run_status,
+        ..Default::default()
+    };
+    queries::insert_pipeline_run(&pipeline_run, db_pool)
+        .await
+        .expect("Failed to insert pipeline run!");
+    let task_instance_id = format!("{}taskinstance", pipeline_run_status);
+    queries::insert_task_instance(
+        models::TaskInstance {
+            id: task_instance_id.clone(),
+            task_id: "task1".to_owned(),
+            pipeline_id: "pipeline1".to_owned(),
+            pipeline_run_id: Some(pipeline_run.id.clone()),
+            scheduled_time: pipeline_run.scheduled_time.clone(),
+            status: task_instance_status,
+            attempt: 1,
+            ..Default::default()
+        },
+        db_pool,
+    )
+    .await
+    .expect("Failed to insert task instance!");
+    task_instance_id
+}
+
+#[tokio::test]
+async fn clear_task_instance_success() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    let task_instance_id = insert_pipeline_run_with_task_instance(
+        models::PipelineRunStatus::Failed,
+        models::TaskStatus::Failed,
+        &db_pool,
+    )
+    .await;
+
+    // Act
+    let url = &format!(
+        "{}/api/task_instances/{}/clear",
+        server_address, task_instance_id
+    );
+    let response = client
+        .post(url)
+        .json(&models::Clear { downstream: false })
+        .send()
+        .await
+        .expect("Failed to POST clear!");
+
+    // Assert that a new attempt is pending within the same, queued again, run
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: JSONResponse<models::TaskInstance> = response.json().await.unwrap();
+    let task_instances = body.data.unwrap();
+    assert_eq!(task_instances.len(), 1);
+    assert_eq!(task_instances[0].task_id, "task1");
+    assert_eq!(task_instances[0].status, models::TaskStatus::Pending);
+    assert_eq!(task_instances[0].attempt, 2);
+    assert_eq!(task_instances[0].scheduled_time, "2023-12-15T09:00:00Z");
+    let pipeline_run = queries::select_pipeline_run_by_id("failedpipelinerun", &db_pool)
+        .await
+        .unwrap();
+    assert_eq!(pipeline_run.status, models::PipelineRunStatus::Queued);
+}
+
+#[tokio::test]
+async fn clear_task_instance_of_active_run_fails() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    let task_instance_id = insert_pipeline_run_with_task_instance(
+        models::PipelineRunStatus::Running,
+        models::TaskStatus::Running,
+        &db_pool,
+    )
+    .await;
+
+    // Act
+    let url = &format!(
+        "{}/api/task_instances/{}/clear",
+        server_address, task_instance_id
+    );
+    let response = client
+        .post(url)
+        .json(&models::Clear { downstream: true })
+        .send()
+        .await
+        .expect("Failed to POST clear!");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn clear_task_instance_not_found() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let url = &format!(
+        "{}/api/task_instances/missingtaskinstance/clear",
+        server_address
+    );
+
+    // Act
+    let response = client
+        .post(url)
+        .json(&models::Clear::default())
+        .send()
+        .await
+        .expect("Failed to POST clear!");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::NOT_FOUND)
+}
+
+#[tokio::test]
+async fn insert_task_instance_attempt_twice_keeps_first() {
+    // Arrange
+    let (_, db_pool) = spawn_app_with_pool().await;
+    let task_instance_id = insert_pipeline_run_with_task_instance(
+        models::PipelineRunStatus::Running,
+        models::TaskStatus::Running,
+        &db_pool,
+    )
+    .await;
+    let task_instance = queries::select_task_instance_by_id(&task_instance_id, &db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let inserted = queries::insert_task_instance(
+        models::TaskInstance {
+            id: "duplicatetaskinstance".to_owned(),
+            status: models::TaskStatus::Queued,
+            ..task_instance.clone()
+        },
+        &db_pool,
+    )
+    .await
+    .expect("Failed to insert task instance!");
+
+    // Assert that the running attempt is left alone
+    assert!(!inserted);
+    let task_instances =
+        queries::select_task_instances_by_pipeline_run_id("runningpipelinerun", &db_pool)
+            .await
+            .unwrap();
+    assert_eq!(task_instances, vec![task_instance]);
+}
+
+#[tokio::test]
+async fn claim_task_instance_prefers_higher_priority_weight() {
+    // Arrange
+    let (_, db_pool) = spawn_app_with_pool().await;
+    for (id, created_at, priority_weight) in [
+        ("lowpriority", "2023-12-29T09:00:00Z", 1),
+        ("highpriority", "2023-12-29T09:00:01Z", 5),
+    ] {
+        queries::insert_task_instance(
+            models::TaskInstance {
+                id: id.to_owned(),
+                task_id: "task1".to_owned(),
+                pipeline_id: "pipeline1".to_owned(),
+                pipeline_run_id: Some(format!("{}pipelinerun", id)),
+                status: models::TaskStatus::Queued,
+                created_at: created_at.to_owned(),
+                attempt: 1,
+                last_attempt: 1,
+                priority_weight,
+                ..Default::default()
+            },
+            &db_pool,
+        )
+        .await
+        .expect("Failed to insert task instance!");
+    }
+
+    // Act
+    let claimed = queries::claim_task_instance(
+        "executor",
+        "2023-12-29T09:00:02Z",
+        "9999-01-01T00:00:00Z",
+        &db_pool,
+    )
+    .await
+    .unwrap();
+
+    // Assert that the newer but more important TaskInstance goes first
+    assert_eq!(claimed.unwrap().id, "highpriority");
+}
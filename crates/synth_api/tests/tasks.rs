@@ -21,6 +21,15 @@ async fn create_one_task_success() {
         id: id.clone(),
         pipeline_id: "testpipeline".to_owned(),
         command: "1 * * * *".to_owned(),
+        depends_on: vec![],
+        retries: 3,
+        retry_delay: 30,
+        retry_backoff: models::RetryBackoff::Exponential,
+        timeout: Some(60),
+        pool: None,
+        pool_slots: 1,
+        priority_weight: 5,
+        weight_rule: models::WeightRule::Absolute,
     };
     let create_response = client
         .post(create_url)
@@ -88,6 +97,50 @@ async fn create_task_failures() {
     }
 }
 
+#[tokio::test]
+async fn create_task_with_dependency_cycle_failure() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let url = &format!("{}/api/tasks", server_address);
+    let upstream = models::Task {
+        id: "upstreamtask".to_owned(),
+        pipeline_id: "cyclepipeline".to_owned(),
+        command: "echo upstream".to_owned(),
+        depends_on: vec!["downstreamtask".to_owned()],
+        ..Default::default()
+    };
+    let downstream = models::Task {
+        id: "downstreamtask".to_owned(),
+        pipeline_id: "cyclepipeline".to_owned(),
+        command: "echo downstream".to_owned(),
+        depends_on: vec!["upstreamtask".to_owned()],
+        ..Default::default()
+    };
+
+    // Act
+    let upstream_response = client
+        .post(url)
+        .json(&upstream)
+        .send()
+        .await
+        .expect("Failed to POST task!");
+    let downstream_response = client
+        .post(url)
+        .json(&downstream)
+        .send()
+        .await
+        .expect("Failed to POST task!");
+
+    // Assert
+    assert_eq!(upstream_response.status(), StatusCode::CREATED);
+    assert_eq!(
+        downstream_response.status(),
+        StatusCode::BAD_REQUEST,
+        "Cyclic Task was accepted!"
+    );
+}
+
 #[tokio::test]
 async fn list_tasks_success() {
     // Arrange
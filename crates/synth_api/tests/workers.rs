@@ -0,0 +1,368 @@
+mod helpers;
+
+use crate::helpers::{spawn_app, spawn_app_with_pool};
+use pretty_assertions::assert_eq;
+use reqwest::{Client, StatusCode};
+use sqlx::SqlitePool;
+use synth_api::models::JSONResponse;
+use synth_common::{models, queries};
+
+/// Register a Worker through the API and return its ID
+async fn register_worker(server_address: &str, client: &Client) -> String {
+    let response = client
+        .post(format!("{}/api/workers", server_address))
+        .json(&models::RegisterWorker {
+            hostname: "testhost".to_owned(),
+        })
+        .send()
+        .await
+        .expect("Failed to POST worker!");
+    let body: JSONResponse<models::Worker> = response.json().await.unwrap();
+    body.data.unwrap().remove(0).id
+}
+
+/// Queue a TaskInstance of `task1` within a running PipelineRun
+async fn queue_task_instance(db_pool: &SqlitePool) -> String {
+    let pipeline_run = models::PipelineRun {
+        id: "workerpipelinerun".to_owned(),
+        pipeline_id: "pipeline1".to_owned(),
+        scheduled_time: "2023-12-15T09:00:00Z".to_owned(),
+        trigger: models::RunTrigger::Manual,
+        status: models::PipelineRunStatus::Running,
+        params: sqlx::types::Json([("who".to_owned(), "me".to_owned())].into()),
+        ..Default::default()
+    };
+    queries::insert_pipeline_run(&pipeline_run, db_pool)
+        .await
+        .expect("Failed to insert pipeline run!");
+    let task_instance_id = "queuedtaskinstance".to_owned();
+    queries::insert_task_instance(
+        models::TaskInstance {
+            id: task_instance_id.clone(),
+            task_id: "task1".to_owned(),
+            pipeline_id: "pipeline1".to_owned(),
+            pipeline_run_id: Some(pipeline_run.id),
+            status: models::TaskStatus::Queued,
+            attempt: 1,
+            last_attempt: 1,
+            ..Default::default()
+        },
+        db_pool,
+    )
+    .await
+    .expect("Failed to insert task instance!");
+    task_instance_id
+}
+
+#[tokio::test]
+async fn register_worker_success() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+
+    // Act
+    let worker_id = register_worker(&server_address, &client).await;
+    let response = client
+        .get(format!("{}/api/workers", server_address))
+        .send()
+        .await
+        .expect("Failed to GET workers!");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: JSONResponse<models::Worker> = response.json().await.unwrap();
+    let workers = body.data.unwrap();
+    assert_eq!(workers.len(), 1);
+    assert_eq!(workers[0].id, worker_id);
+    assert_eq!(workers[0].hostname, "testhost");
+}
+
+#[tokio::test]
+async fn heartbeat_unknown_worker_fails() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+
+    // Act
+    let response = client
+        .post(format!(
+            "{}/api/workers/missingworker/heartbeat",
+            server_address
+        ))
+        .json(&models::WorkerHeartbeat::default())
+        .send()
+        .await
+        .expect("Failed to POST heartbeat!");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn poll_without_queued_task_instances_returns_nothing() {
+    // Arrange
+    let server_address = spawn_app().await;
+    let client = Client::new();
+    let worker_id = register_worker(&server_address, &client).await;
+
+    // Act
+    let response = client
+        .get(format!(
+            "{}/api/workers/{}/task?wait=0",
+            server_address, worker_id
+        ))
+        .send()
+        .await
+        .expect("Failed to GET task!");
+
+    // Assert
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: JSONResponse<models::TaskAssignment> = response.json().await.unwrap();
+    assert_eq!(body.data.unwrap().len(), 0);
+}
+
+#[tokio::test]
+async fn poll_and_report_result_success() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    let worker_id = register_worker(&server_address, &client).await;
+    let task_instance_id = queue_task_instance(&db_pool).await;
+
+    // Act
+    let poll_response = client
+        .get(format!(
+            "{}/api/workers/{}/task?wait=0",
+            server_address, worker_id
+        ))
+        .send()
+        .await
+        .expect("Failed to GET task!");
+    let logs_response = client
+        .post(format!(
+            "{}/api/workers/{}/task_instances/{}/logs",
+            server_address, worker_id, task_instance_id
+        ))
+        .json(&vec![models::TaskLog {
+            timestamp: "2023-12-15T09:00:01Z".to_owned(),
+            stream: "stdout".to_owned(),
+            line: "some logs".to_owned(),
+            ..Default::default()
+        }])
+        .send()
+        .await
+        .expect("Failed to POST logs!");
+    let result_response = client
+        .post(format!(
+            "{}/api/workers/{}/task_instances/{}/result",
+            server_address, worker_id, task_instance_id
+        ))
+        .json(&models::TaskResult {
+            status: models::TaskStatus::Success,
+            exit_code: Some(0),
+        })
+        .send()
+        .await
+        .expect("Failed to POST result!");
+
+    // Assert that the Worker got everything needed to run the Task
+    assert_eq!(poll_response.status(), StatusCode::OK);
+    let body: JSONResponse<models::TaskAssignment> = poll_response.json().await.unwrap();
+    let assignment = body.data.unwrap().remove(0);
+    assert_eq!(assignment.task_instance.id, task_instance_id);
+    assert_eq!(assignment.task_instance.status, models::TaskStatus::Running);
+    assert_eq!(assignment.command, "sleep 6 && echo some logs");
+    assert_eq!(assignment.params.get("who").unwrap(), "me");
+
+    assert_eq!(logs_response.status(), StatusCode::CREATED);
+    let task_logs = queries::select_task_logs_by_task_instance_id(&task_instance_id, &db_pool)
+        .await
+        .unwrap();
+    assert_eq!(task_logs.len(), 1);
+    assert_eq!(task_logs[0].line, "some logs");
+
+    assert_eq!(result_response.status(), StatusCode::OK);
+    let body: JSONResponse<models::TaskInstance> = result_response.json().await.unwrap();
+    let task_instance = body.data.unwrap().remove(0);
+    assert_eq!(task_instance.status, models::TaskStatus::Success);
+    assert_eq!(task_instance.exit_code, Some(0));
+}
+
+#[tokio::test]
+async fn report_result_of_task_instance_held_by_another_worker_fails() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    let worker_id = register_worker(&server_address, &client).await;
+    let other_worker_id = register_worker(&server_address, &client).await;
+    let task_instance_id = queue_task_instance(&db_pool).await;
+    client
+        .get(format!(
+            "{}/api/workers/{}/task?wait=0",
+            server_address, worker_id
+        ))
+        .send()
+        .await
+        .expect("Failed to GET task!");
+
+    // Act
+    let result_response = client
+        .post(format!(
+            "{}/api/workers/{}/task_instances/{}/result",
+            server_address, other_worker_id, task_instance_id
+        ))
+        .json(&models::TaskResult {
+            status: models::TaskStatus::Success,
+            exit_code: Some(0),
+        })
+        .send()
+        .await
+        .expect("Failed to POST result!");
+    let heartbeat_response = client
+        .post(format!(
+            "{}/api/workers/{}/heartbeat",
+            server_address, other_worker_id
+        ))
+        .json(&models::WorkerHeartbeat {
+            running: vec![task_instance_id.clone()],
+        })
+        .send()
+        .await
+        .expect("Failed to POST heartbeat!");
+
+    // Assert that the other Worker is told to stop the TaskInstance
+    assert_eq!(result_response.status(), StatusCode::CONFLICT);
+    assert_eq!(heartbeat_response.status(), StatusCode::OK);
+    let body: JSONResponse<String> = heartbeat_response.json().await.unwrap();
+    assert_eq!(body.data.unwrap(), vec![task_instance_id]);
+}
+
+#[tokio::test]
+async fn heartbeat_for_zombie_task_instance_stops_it() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    let worker_id = register_worker(&server_address, &client).await;
+    let task_instance_id = queue_task_instance(&db_pool).await;
+    client
+        .get(format!(
+            "{}/api/workers/{}/task?wait=0",
+            server_address, worker_id
+        ))
+        .send()
+        .await
+        .expect("Failed to GET task!");
+    // Pretend the Worker stopped sending heartbeats until its lease expired
+    let zombies = queries::mark_zombie_task_instances("9999-01-01T00:00:00Z", &db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let heartbeat_response = client
+        .post(format!(
+            "{}/api/workers/{}/heartbeat",
+            server_address, worker_id
+        ))
+        .json(&models::WorkerHeartbeat {
+            running: vec![task_instance_id.clone()],
+        })
+        .send()
+        .await
+        .expect("Failed to POST heartbeat!");
+
+    // Assert that the Worker is told to stop the zombie
+    assert_eq!(zombies, vec![task_instance_id.clone()]);
+    assert_eq!(heartbeat_response.status(), StatusCode::OK);
+    let body: JSONResponse<String> = heartbeat_response.json().await.unwrap();
+    assert_eq!(body.data.unwrap(), vec![task_instance_id.clone()]);
+    let task_instance = queries::select_task_instance_by_id(&task_instance_id, &db_pool)
+        .await
+        .unwrap();
+    assert_eq!(task_instance.status, models::TaskStatus::Zombie);
+    assert!(task_instance.last_heartbeat_at.is_some());
+}
+
+#[tokio::test]
+async fn heartbeat_only_renews_reported_task_instances() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    let worker_id = register_worker(&server_address, &client).await;
+    let task_instance_id = queue_task_instance(&db_pool).await;
+    client
+        .get(format!(
+            "{}/api/workers/{}/task?wait=0",
+            server_address, worker_id
+        ))
+        .send()
+        .await
+        .expect("Failed to GET task!");
+    // Let the lease run out, as if the Worker lost track of the TaskInstance
+    queries::renew_task_instance_lease(
+        &task_instance_id,
+        &worker_id,
+        "2000-01-01T00:00:00Z",
+        "2000-01-01T00:00:30Z",
+        &db_pool,
+    )
+    .await
+    .unwrap();
+
+    // Act
+    let heartbeat_response = client
+        .post(format!(
+            "{}/api/workers/{}/heartbeat",
+            server_address, worker_id
+        ))
+        .json(&models::WorkerHeartbeat { running: vec![] })
+        .send()
+        .await
+        .expect("Failed to POST heartbeat!");
+    let zombies = queries::mark_zombie_task_instances("2000-01-01T00:01:00Z", &db_pool)
+        .await
+        .unwrap();
+
+    // Assert that the lease of the unreported TaskInstance wasn't renewed
+    assert_eq!(heartbeat_response.status(), StatusCode::OK);
+    let body: JSONResponse<String> = heartbeat_response.json().await.unwrap();
+    assert_eq!(body.data.unwrap().len(), 0);
+    assert_eq!(zombies, vec![task_instance_id]);
+}
+
+#[tokio::test]
+async fn report_result_other_than_success_or_failed_fails() {
+    // Arrange
+    let (server_address, db_pool) = spawn_app_with_pool().await;
+    let client = Client::new();
+    let worker_id = register_worker(&server_address, &client).await;
+    let task_instance_id = queue_task_instance(&db_pool).await;
+    client
+        .get(format!(
+            "{}/api/workers/{}/task?wait=0",
+            server_address, worker_id
+        ))
+        .send()
+        .await
+        .expect("Failed to GET task!");
+
+    // Act
+    let result_response = client
+        .post(format!(
+            "{}/api/workers/{}/task_instances/{}/result",
+            server_address, worker_id, task_instance_id
+        ))
+        .json(&models::TaskResult {
+            status: models::TaskStatus::Zombie,
+            exit_code: None,
+        })
+        .send()
+        .await
+        .expect("Failed to POST result!");
+
+    // Assert that the TaskInstance is still running
+    assert_eq!(result_response.status(), StatusCode::BAD_REQUEST);
+    let task_instance = queries::select_task_instance_by_id(&task_instance_id, &db_pool)
+        .await
+        .unwrap();
+    assert_eq!(task_instance.status, models::TaskStatus::Running);
+}
@@ -1,8 +1,244 @@
 use super::{manifests, models, utils};
+use chrono::Utc;
 use clap::ArgMatches;
+use serde_json::json;
+use std::collections::HashMap;
+use synth_common::config::CalendarSettings;
+use synth_common::models::{Backfill, TaskLog, Trigger};
+use synth_common::{calendar, schedule};
 
 pub fn check(sub_matches: &ArgMatches) -> models::Manifest {
     let filepath = sub_matches.get_one::<String>("filepath").unwrap();
     let raw_manifest = utils::load_file(filepath);
-    manifests::parse_manifest_file(raw_manifest)
+    let manifest = manifests::parse_manifest_file(raw_manifest);
+
+    if let Err(e) = manifests::validate_manifest(&manifest) {
+        println!("> {}", e);
+        std::process::exit(2)
+    }
+    manifest
+}
+
+/// Show the next times that each of a manifest's Pipelines is scheduled to run,
+/// avoiding the holidays of the calendars in the config
+pub fn print_next_fire_times(
+    manifest: &models::Manifest,
+    count: usize,
+    calendars: &HashMap<String, CalendarSettings>,
+) {
+    let now = Utc::now();
+    for pipeline in &manifest.pipelines {
+        let fire_times = pipeline
+            .to_schedule()
+            .and_then(|schedule| match &pipeline.calendar {
+                Some(id) => {
+                    let settings = calendars
+                        .get(id)
+                        .ok_or_else(|| format!("Calendar '{}' isn't in the config!", id))?;
+                    let holidays = calendar::load_holidays(settings)?;
+                    schedule.with_holidays(&holidays, pipeline.holiday_policy)
+                }
+                None => Ok(schedule),
+            })
+            .and_then(|schedule| schedule.next_fire_times(&now, count));
+        match fire_times {
+            Ok(fire_times) if fire_times.is_empty() => {
+                println!("> '{}' has no upcoming runs!", pipeline.id)
+            }
+            Ok(fire_times) => {
+                println!(
+                    "> '{}' ({}) runs next at:",
+                    pipeline.id,
+                    pipeline.schedule_expression()
+                );
+                for fire_time in fire_times {
+                    let fire_time = schedule::format_timestamp(&fire_time);
+                    let local_time =
+                        schedule::format_local_timestamp(&fire_time, pipeline.timezone.as_deref());
+                    println!("    {}", local_time);
+                }
+            }
+            Err(e) => println!("> Invalid Pipeline '{}': {}", pipeline.id, e),
+        }
+    }
+}
+
+/// Queue runs of a Pipeline for every schedule tick in a range
+pub async fn backfill(server_url: &str, sub_matches: &ArgMatches) {
+    let pipeline_id = sub_matches.get_one::<String>("pipeline").unwrap();
+    let backfill = json!(Backfill {
+        start: sub_matches.get_one::<String>("start").unwrap().to_owned(),
+        end: sub_matches.get_one::<String>("end").unwrap().to_owned(),
+    });
+    let url = format!("{}/api/pipelines/{}/backfill", server_url, pipeline_id);
+
+    match utils::post_json(&url, &backfill).await {
+        Ok(response) => {
+            let body: serde_json::Value = response.json().await.unwrap_or_default();
+            let queued = body["data"].as_array().map_or(0, |runs| runs.len());
+            println!("> Queued {} backfill run(s) for '{}'!", queued, pipeline_id);
+        }
+        Err(e) => {
+            println!("> Failed POST to '{}'!", &url);
+            println!("{:?}", e);
+        }
+    }
+}
+
+/// Queue a manual run of a Pipeline
+pub async fn trigger(server_url: &str, sub_matches: &ArgMatches) {
+    let pipeline_id = sub_matches.get_one::<String>("pipeline").unwrap();
+    let mut params = HashMap::new();
+    for param in sub_matches.get_many::<String>("param").unwrap_or_default() {
+        let Some((name, value)) = param.split_once('=') else {
+            println!("> Parameter '{}' must be formatted as NAME=VALUE!", param);
+            std::process::exit(2)
+        };
+        params.insert(name.to_owned(), value.to_owned());
+    }
+    let trigger = json!(Trigger {
+        logical_date: sub_matches.get_one::<String>("logical-date").cloned(),
+        params,
+    });
+    let url = format!("{}/api/pipelines/{}/trigger", server_url, pipeline_id);
+
+    match utils::post_json(&url, &trigger).await {
+        Ok(response) => {
+            let body: serde_json::Value = response.json().await.unwrap_or_default();
+            println!(
+                "> Triggered run '{}' of '{}'!",
+                body["data"][0]["id"].as_str().unwrap_or("?"),
+                pipeline_id
+            );
+        }
+        Err(e) => {
+            println!("> Failed POST to '{}'!", &url);
+            println!("{:?}", e);
+        }
+    }
+}
+
+/// Pause or resume a Pipeline
+pub async fn set_paused(server_url: &str, sub_matches: &ArgMatches, is_paused: bool) {
+    let pipeline_id = sub_matches.get_one::<String>("pipeline").unwrap();
+    let action = if is_paused { "pause" } else { "resume" };
+    let url = format!("{}/api/pipelines/{}/{}", server_url, pipeline_id, action);
+
+    match utils::post_json(&url, &json!({})).await {
+        Ok(_) if is_paused => println!("> Paused '{}'!", pipeline_id),
+        Ok(_) => println!("> Resumed '{}'!", pipeline_id),
+        Err(e) => {
+            println!("> Failed POST to '{}'!", &url);
+            println!("{:?}", e);
+        }
+    }
+}
+
+/// Cancel a TaskInstance or a whole PipelineRun
+pub async fn cancel(server_url: &str, sub_matches: &ArgMatches) {
+    let id = sub_matches.get_one::<String>("id").unwrap();
+    let resource = if sub_matches.get_flag("pipeline-run") {
+        "pipeline_runs"
+    } else {
+        "task_instances"
+    };
+    let url = format!("{}/api/{}/{}/cancel", server_url, resource, id);
+
+    match utils::post_json(&url, &json!({})).await {
+        Ok(_) => println!("> Cancelled '{}'!", id),
+        Err(e) => {
+            println!("> Failed POST to '{}'!", &url);
+            println!("{:?}", e);
+        }
+    }
+}
+
+/// Clear a TaskInstance so that its Task, and optionally its downstream Tasks, run again
+pub async fn clear(server_url: &str, sub_matches: &ArgMatches) {
+    let task_instance_id = sub_matches.get_one::<String>("task_instance").unwrap();
+    let body = json!({ "downstream": sub_matches.get_flag("downstream") });
+    let url = format!(
+        "{}/api/task_instances/{}/clear",
+        server_url, task_instance_id
+    );
+
+    match utils::post_json(&url, &body).await {
+        Ok(_) => println!("> Cleared '{}'!", task_instance_id),
+        Err(e) => {
+            println!("> Failed POST to '{}'!", &url);
+            println!("{:?}", e);
+        }
+    }
+}
+
+/// Print the log lines of a TaskInstance, optionally following them live
+pub async fn logs(server_url: &str, sub_matches: &ArgMatches) {
+    let task_instance_id = sub_matches.get_one::<String>("task_instance").unwrap();
+    let result = if sub_matches.get_flag("follow") {
+        follow_logs(server_url, task_instance_id).await
+    } else {
+        print_logs(server_url, task_instance_id).await
+    };
+
+    if let Err(e) = result {
+        println!("> Failed to fetch logs for '{}'!", task_instance_id);
+        println!("{:?}", e);
+        std::process::exit(2)
+    }
+}
+
+/// Print every log line stored so far
+async fn print_logs(server_url: &str, task_instance_id: &str) -> Result<(), reqwest::Error> {
+    let url = format!(
+        "{}/api/task_instances/{}/logs",
+        server_url, task_instance_id
+    );
+    let body: serde_json::Value = reqwest::get(&url).await?.error_for_status()?.json().await?;
+    let task_logs: Vec<TaskLog> = serde_json::from_value(body["data"].clone()).unwrap_or_default();
+    for task_log in task_logs {
+        println!("{}", task_log);
+    }
+    Ok(())
+}
+
+/// Tail the log stream until the TaskInstance finishes
+async fn follow_logs(server_url: &str, task_instance_id: &str) -> Result<(), reqwest::Error> {
+    let url = format!(
+        "{}/api/task_instances/{}/logs/stream",
+        server_url, task_instance_id
+    );
+    let mut response = reqwest::get(&url).await?.error_for_status()?;
+
+    // Events are separated by a blank line and may be split across chunks
+    let mut buffer = String::new();
+    while let Some(chunk) = response.chunk().await? {
+        buffer.push_str(&String::from_utf8_lossy(&chunk));
+        while let Some(end) = buffer.find("\n\n") {
+            let event: String = buffer.drain(..end + 2).collect();
+            let mut name = "message";
+            let mut data = "";
+            for line in event.lines() {
+                if let Some(value) = line.strip_prefix("event: ") {
+                    name = value;
+                } else if let Some(value) = line.strip_prefix("data: ") {
+                    data = value;
+                }
+            }
+
+            match name {
+                "log" => {
+                    if let Ok(task_log) = serde_json::from_str::<TaskLog>(data) {
+                        println!("{}", task_log);
+                    }
+                }
+                "end" => {
+                    println!("> Task instance finished with status '{}'", data);
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+    }
+    println!("> Log stream closed before the task instance finished");
+    Ok(())
 }
@@ -1,5 +1,5 @@
 use super::{commands, entrypoint, register, utils};
-use clap::{crate_version, Arg, Command};
+use clap::{crate_version, Arg, ArgAction, Command};
 use synth_common::config::{load_config, BuildUrl};
 use synth_common::database;
 
@@ -29,26 +29,145 @@ pub fn cli_builder() -> Command {
                 .help("Path to a Synthesizer Config File"),
         )
         // Add Subcommands
+        .subcommand(
+            Command::new("backfill")
+                .about("Queue runs of a pipeline for every schedule tick in a range.")
+                .arg(
+                    Arg::new("pipeline")
+                        .required(true)
+                        .help("ID of the Pipeline."),
+                )
+                .arg(
+                    Arg::new("start")
+                        .long("start")
+                        .required(true)
+                        .help("RFC 3339 start of the range, inclusive."),
+                )
+                .arg(
+                    Arg::new("end")
+                        .long("end")
+                        .required(true)
+                        .help("RFC 3339 end of the range, inclusive."),
+                ),
+        )
+        .subcommand(
+            Command::new("cancel")
+                .about("Stop a running task instance, or a whole pipeline run.")
+                .arg(
+                    Arg::new("id").required(true).help(
+                        "ID of the Task Instance, or of the Pipeline Run with --pipeline-run.",
+                    ),
+                )
+                .arg(
+                    Arg::new("pipeline-run")
+                        .long("pipeline-run")
+                        .action(ArgAction::SetTrue)
+                        .help("Cancel a Pipeline Run and all of its Task Instances."),
+                ),
+        )
         .subcommand(
             Command::new("check")
                 .about("Check that Synthesizer files are valid.")
-                .arg(&manifest_filepath),
+                .arg(&manifest_filepath)
+                .arg(
+                    Arg::new("next")
+                        .long("next")
+                        .short('n')
+                        .default_value("3")
+                        .value_parser(clap::value_parser!(usize))
+                        .help("Number of upcoming fire times to show for each pipeline."),
+                ),
+        )
+        .subcommand(
+            Command::new("clear")
+                .about("Re-run a finished task instance within its pipeline run.")
+                .arg(
+                    Arg::new("task_instance")
+                        .required(true)
+                        .help("ID of the Task Instance."),
+                )
+                .arg(
+                    Arg::new("downstream")
+                        .long("downstream")
+                        .action(ArgAction::SetTrue)
+                        .help("Also re-run every task downstream of it."),
+                ),
         )
         .subcommand(Command::new("config").about("Show the config values that are being used."))
+        .subcommand(Command::new("executor").about("Start a Synth executor."))
+        .subcommand(
+            Command::new("logs")
+                .about("Show the log lines of a task instance.")
+                .arg(
+                    Arg::new("task_instance")
+                        .required(true)
+                        .help("ID of the Task Instance."),
+                )
+                .arg(
+                    Arg::new("follow")
+                        .long("follow")
+                        .short('f')
+                        .action(ArgAction::SetTrue)
+                        .help("Keep streaming new lines until the task instance finishes."),
+                ),
+        )
         .subcommand(
             Command::new("ls")
                 .about("List resources from the server.")
                 .arg_required_else_help(true),
         )
+        .subcommand(
+            Command::new("pause")
+                .about("Stop a pipeline from being scheduled.")
+                .arg(
+                    Arg::new("pipeline")
+                        .required(true)
+                        .help("ID of the Pipeline."),
+                ),
+        )
         .subcommand(
             Command::new("register")
                 .about("Upsert pipeline(s) to the server.")
                 .arg(&manifest_filepath),
         )
+        .subcommand(
+            Command::new("resume")
+                .about("Let a paused pipeline be scheduled again.")
+                .arg(
+                    Arg::new("pipeline")
+                        .required(true)
+                        .help("ID of the Pipeline."),
+                ),
+        )
         .subcommand(Command::new("scheduler").about("Start the Synth scheduler."))
         .subcommand(Command::new("setupdb").about("Create the database and run migrations."))
         .subcommand(Command::new("status").about("Ping the webserver."))
+        .subcommand(
+            Command::new("trigger")
+                .about("Queue a manual run of a pipeline.")
+                .arg(
+                    Arg::new("pipeline")
+                        .required(true)
+                        .help("ID of the Pipeline."),
+                )
+                .arg(
+                    Arg::new("logical-date")
+                        .long("logical-date")
+                        .help("RFC 3339 time that the run is for. Defaults to now."),
+                )
+                .arg(
+                    Arg::new("param")
+                        .long("param")
+                        .short('p')
+                        .action(ArgAction::Append)
+                        .help("A NAME=VALUE parameter passed to every task. May be repeated."),
+                ),
+        )
         .subcommand(Command::new("webserver").about("Start the Synth API Webserver."))
+        .subcommand(
+            Command::new("worker")
+                .about("Start a remote worker that runs tasks through the API of the server."),
+        )
 }
 
 pub async fn run() {
@@ -65,21 +184,34 @@ pub async fn run() {
                 .await
                 .expect("Failed to setup the database!");
         }
+        Some(("backfill", sub_matches)) => commands::backfill(&server_url, sub_matches).await,
+        Some(("cancel", sub_matches)) => commands::cancel(&server_url, sub_matches).await,
         Some(("check", sub_matches)) => {
             let manifest = commands::check(sub_matches);
             println!(
                 "> Successfully parsed {} pipeline(s)!",
                 manifest.pipelines.len()
             );
+            let count = *sub_matches.get_one::<usize>("next").unwrap();
+            commands::print_next_fire_times(&manifest, count, &config.calendars);
         }
+        Some(("clear", sub_matches)) => commands::clear(&server_url, sub_matches).await,
+        Some(("logs", sub_matches)) => commands::logs(&server_url, sub_matches).await,
         Some(("config", _)) => println!("> Config Values:\n{:#?}", config),
         Some(("webserver", _)) => synth_api::start().await,
         Some(("scheduler", _)) => synth_scheduler::start().await,
+        Some(("executor", _)) => synth_executor::start().await,
+        Some(("worker", _)) => synth_executor::start_worker(server_url, config.worker).await,
         Some(("status", _)) => {
             if utils::check_url_reachable_and_success(&server_url) {
                 println! {"Server is reachable!"}
             }
         }
+        Some(("pause", sub_matches)) => commands::set_paused(&server_url, sub_matches, true).await,
+        Some(("resume", sub_matches)) => {
+            commands::set_paused(&server_url, sub_matches, false).await
+        }
+        Some(("trigger", sub_matches)) => commands::trigger(&server_url, sub_matches).await,
         Some(("register", sub_matches)) => {
             let manifest = commands::check(sub_matches);
             register::register(&server_url, manifest).await;
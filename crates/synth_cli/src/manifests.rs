@@ -1,4 +1,5 @@
 use crate::models;
+use synth_common::dag;
 
 pub fn parse_manifest_file(contents: String) -> models::Manifest {
     let roxfile_result = serde_yaml::from_str(&contents);
@@ -11,3 +12,27 @@ pub fn parse_manifest_file(contents: String) -> models::Manifest {
         }
     }
 }
+
+/// Validate the contents of a parsed manifest
+pub fn validate_manifest(manifest: &models::Manifest) -> Result<(), String> {
+    for pipeline in &manifest.pipelines {
+        if pipeline.max_active_runs.is_some_and(|max| max < 1) {
+            return Err(format!(
+                "Invalid Pipeline '{}': max_active_runs must be at least 1!",
+                pipeline.id
+            ));
+        }
+        pipeline
+            .to_schedule()
+            .map_err(|e| format!("Invalid Pipeline '{}': {}", pipeline.id, e))?;
+        if let Some(task) = pipeline.tasks.iter().find(|task| task.pool_slots < 1) {
+            return Err(format!(
+                "Invalid Task '{}': pool_slots must be at least 1!",
+                task.id
+            ));
+        }
+        dag::validate(&pipeline.to_tasks())
+            .map_err(|e| format!("Invalid Pipeline '{}': {}", pipeline.id, e))?;
+    }
+    Ok(())
+}
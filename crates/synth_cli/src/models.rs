@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use synth_common::models::{self, HolidayPolicy, OverlapPolicy, RetryBackoff, Task, WeightRule};
+use synth_common::schedule::{self, Schedule};
 
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct Manifest {
@@ -8,12 +10,117 @@ pub struct Manifest {
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct ManifestPipeline {
     pub id: String,
-    pub schedule: String,
+    /// A cron expression, optionally with seconds, or a preset such as `@daily`
+    #[serde(default)]
+    pub schedule: Option<String>,
+    /// Run at a fixed interval such as `15m` instead of on a cron schedule
+    #[serde(default)]
+    pub every: Option<String>,
+    /// IANA name of the time zone that the schedule is evaluated in, e.g.
+    /// `Europe/Berlin`. Defaults to UTC
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// The first time the schedule may fire, which intervals are counted from
+    #[serde(default)]
+    pub start_date: Option<String>,
+    /// The last time the schedule may fire
+    #[serde(default)]
+    pub end_date: Option<String>,
+    /// Run every schedule tick that was missed while the scheduler was down
+    #[serde(default)]
+    pub catchup: bool,
+    /// Seconds a Task may run before it is killed, unless the Task sets its own
+    #[serde(default)]
+    pub default_timeout: Option<i64>,
+    /// Register the Pipeline paused. Has no effect once the Pipeline exists
+    #[serde(default)]
+    pub is_paused: bool,
+    /// How many runs of the Pipeline may be running at once
+    #[serde(default)]
+    pub max_active_runs: Option<i64>,
+    /// Either `queue`, `skip` or `cancel_previous`
+    #[serde(default)]
+    pub overlap_policy: OverlapPolicy,
+    /// Name of a business calendar from the config, whose holidays are avoided
+    #[serde(default)]
+    pub calendar: Option<String>,
+    /// Either `skip_on_holiday` or `next_business_day`
+    #[serde(default)]
+    pub holiday_policy: HolidayPolicy,
     pub tasks: Vec<ManifestTask>,
 }
+impl ManifestPipeline {
+    /// The schedule that the Pipeline is registered with
+    pub fn schedule_expression(&self) -> String {
+        match &self.every {
+            Some(every) => format!("{}{}", schedule::EVERY_PREFIX, every),
+            None => self.schedule.clone().unwrap_or_default(),
+        }
+    }
+
+    /// Parse the Pipeline's schedule, along with its time zone and bounds,
+    /// leaving out its calendar
+    pub fn to_schedule(&self) -> Result<Schedule, String> {
+        if self.schedule.is_some() == self.every.is_some() {
+            return Err("Set exactly one of schedule and every!".to_string());
+        }
+        Schedule::parse(
+            &self.schedule_expression(),
+            self.timezone.as_deref(),
+            self.start_date.as_deref(),
+            self.end_date.as_deref(),
+        )
+    }
+
+    /// Convert the manifest's Tasks into Task models for this Pipeline
+    pub fn to_tasks(&self) -> Vec<Task> {
+        self.tasks
+            .iter()
+            .map(|task| Task {
+                id: task.id.clone(),
+                pipeline_id: self.id.clone(),
+                command: task.command.clone(),
+                depends_on: task.depends_on.clone(),
+                retries: task.retries,
+                retry_delay: task.retry_delay,
+                retry_backoff: task.retry_backoff,
+                timeout: task.timeout,
+                pool: task.pool.clone(),
+                pool_slots: task.pool_slots,
+                priority_weight: task.priority_weight,
+                weight_rule: task.weight_rule,
+            })
+            .collect()
+    }
+}
 
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct ManifestTask {
     pub id: String,
     pub command: String,
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default)]
+    pub retries: u32,
+    /// Seconds to wait before the first retry
+    #[serde(default)]
+    pub retry_delay: u32,
+    /// Either `fixed` or `exponential`
+    #[serde(default)]
+    pub retry_backoff: RetryBackoff,
+    /// Seconds the Task may run before it is killed
+    #[serde(default)]
+    pub timeout: Option<u32>,
+    /// Name of a pool that limits how many Tasks in it run at once
+    #[serde(default)]
+    pub pool: Option<String>,
+    /// How many of the pool's slots the Task takes up while running
+    #[serde(default = "models::default_pool_slots")]
+    pub pool_slots: u32,
+    /// How urgently the Task runs compared to other queued Tasks
+    #[serde(default = "models::default_priority_weight")]
+    pub priority_weight: u32,
+    /// Either `downstream`, `upstream` or `absolute`
+    #[serde(default)]
+    pub weight_rule: WeightRule,
 }
@@ -1,7 +1,7 @@
 use super::models::Manifest;
 use super::utils;
 use serde_json::{json, Value};
-use synth_common::models::{Pipeline, Task};
+use synth_common::models::Pipeline;
 
 /// Send the objects within the manifest to the webserver.
 pub async fn register(url: &str, manifest: Manifest) -> bool {
@@ -10,7 +10,18 @@ pub async fn register(url: &str, manifest: Manifest) -> bool {
     for manifest_pipeline in manifest.pipelines {
         let pipeline = json!(Pipeline {
             id: manifest_pipeline.id.clone(),
-            schedule: manifest_pipeline.schedule,
+            schedule: manifest_pipeline.schedule_expression(),
+            timezone: manifest_pipeline.timezone.clone(),
+            start_date: manifest_pipeline.start_date.clone(),
+            end_date: manifest_pipeline.end_date.clone(),
+            catchup: manifest_pipeline.catchup,
+            default_timeout: manifest_pipeline.default_timeout,
+            is_paused: manifest_pipeline.is_paused,
+            max_active_runs: manifest_pipeline.max_active_runs,
+            overlap_policy: manifest_pipeline.overlap_policy,
+            calendar: manifest_pipeline.calendar.clone(),
+            holiday_policy: manifest_pipeline.holiday_policy,
+            ..Default::default()
         });
         let result = utils::post_json(&pipeline_url, &pipeline).await;
         match result {
@@ -22,15 +33,9 @@ pub async fn register(url: &str, manifest: Manifest) -> bool {
         }
 
         let tasks: Vec<Value> = manifest_pipeline
-            .tasks
+            .to_tasks()
             .into_iter()
-            .map(|task| {
-                json!(Task {
-                    id: task.id,
-                    pipeline_id: manifest_pipeline.id.clone(),
-                    command: task.command,
-                })
-            })
+            .map(|task| json!(task))
             .collect();
 
         for task in tasks {
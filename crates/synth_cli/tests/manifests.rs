@@ -1,4 +1,5 @@
 use synth_cli::{manifests, utils};
+use synth_common::{calendar, dag};
 
 #[test]
 fn check_default_manifest() {
@@ -6,4 +7,198 @@ fn check_default_manifest() {
     let raw_manifest = utils::load_file(filepath);
     let manifest = manifests::parse_manifest_file(raw_manifest);
     assert!(!manifest.pipelines.is_empty());
+    assert!(manifests::validate_manifest(&manifest).is_ok());
+}
+
+#[test]
+fn check_manifest_with_dependency_cycle_fails() {
+    let raw_manifest = r#"
+pipelines:
+  - id: cyclic_pipeline
+    schedule: "1 * * * *"
+    tasks:
+      - id: task1
+        command: echo "task1"
+        depends_on: [task3]
+      - id: task2
+        command: echo "task2"
+        depends_on: [task1]
+      - id: task3
+        command: echo "task3"
+        depends_on: [task2]
+"#;
+    let manifest = manifests::parse_manifest_file(raw_manifest.to_string());
+    assert!(manifests::validate_manifest(&manifest).is_err());
+}
+
+#[test]
+fn check_manifest_with_unknown_dependency_fails() {
+    let raw_manifest = r#"
+pipelines:
+  - id: missing_pipeline
+    schedule: "1 * * * *"
+    tasks:
+      - id: task1
+        command: echo "task1"
+        depends_on: [task0]
+"#;
+    let manifest = manifests::parse_manifest_file(raw_manifest.to_string());
+    assert!(manifests::validate_manifest(&manifest).is_err());
+}
+
+#[test]
+fn check_manifest_without_active_runs_fails() {
+    let raw_manifest = r#"
+pipelines:
+  - id: overlapping_pipeline
+    schedule: "1 * * * *"
+    max_active_runs: 0
+    overlap_policy: skip
+    tasks:
+      - id: task1
+        command: echo "task1"
+"#;
+    let manifest = manifests::parse_manifest_file(raw_manifest.to_string());
+    assert!(manifests::validate_manifest(&manifest).is_err());
+}
+
+#[test]
+fn manifest_with_unknown_timezone_fails() {
+    let raw_manifest = r#"
+pipelines:
+  - id: zoned_pipeline
+    schedule: "0 9 * * *"
+    timezone: Europe/Atlantis
+    tasks:
+      - id: task1
+        command: echo "task1"
+"#;
+    let manifest = manifests::parse_manifest_file(raw_manifest.to_string());
+    assert!(manifests::validate_manifest(&manifest).is_err());
+}
+
+#[test]
+fn manifest_interval_schedule() {
+    let raw_manifest = r#"
+pipelines:
+  - id: interval_pipeline
+    every: 15m
+    start_date: "2024-01-01"
+    end_date: "2024-12-31"
+    tasks:
+      - id: task1
+        command: echo "task1"
+"#;
+    let manifest = manifests::parse_manifest_file(raw_manifest.to_string());
+    assert!(manifests::validate_manifest(&manifest).is_ok());
+    assert_eq!(manifest.pipelines[0].schedule_expression(), "@every 15m");
+}
+
+#[test]
+fn manifest_with_schedule_and_interval_fails() {
+    let raw_manifest = r#"
+pipelines:
+  - id: interval_pipeline
+    schedule: "@hourly"
+    every: 15m
+    tasks:
+      - id: task1
+        command: echo "task1"
+"#;
+    let manifest = manifests::parse_manifest_file(raw_manifest.to_string());
+    assert!(manifests::validate_manifest(&manifest).is_err());
+}
+
+#[test]
+fn manifest_priority_weights_follow_weight_rules() {
+    let raw_manifest = r#"
+pipelines:
+  - id: weighted_pipeline
+    schedule: "1 * * * *"
+    tasks:
+      - id: extract
+        command: echo "extract"
+      - id: transform
+        command: echo "transform"
+        priority_weight: 2
+        depends_on: [extract]
+      - id: load
+        command: echo "load"
+        priority_weight: 3
+        weight_rule: upstream
+        depends_on: [transform]
+      - id: report
+        command: echo "report"
+        priority_weight: 10
+        weight_rule: absolute
+        depends_on: [transform]
+"#;
+    let manifest = manifests::parse_manifest_file(raw_manifest.to_string());
+    let priority_weights = dag::priority_weights(&manifest.pipelines[0].to_tasks());
+    assert_eq!(priority_weights["extract"], 16);
+    assert_eq!(priority_weights["transform"], 15);
+    assert_eq!(priority_weights["load"], 6);
+    assert_eq!(priority_weights["report"], 10);
+}
+
+#[test]
+fn manifest_priority_weights_count_shared_tasks_once() {
+    let raw_manifest = r#"
+pipelines:
+  - id: diamond_pipeline
+    schedule: "1 * * * *"
+    tasks:
+      - id: merge
+        command: echo "merge"
+        weight_rule: upstream
+        depends_on: [left, right]
+      - id: left
+        command: echo "left"
+        depends_on: [split]
+      - id: right
+        command: echo "right"
+        depends_on: [split]
+      - id: split
+        command: echo "split"
+"#;
+    let manifest = manifests::parse_manifest_file(raw_manifest.to_string());
+    let priority_weights = dag::priority_weights(&manifest.pipelines[0].to_tasks());
+    assert_eq!(priority_weights["split"], 4);
+    assert_eq!(priority_weights["left"], 2);
+    assert_eq!(priority_weights["right"], 2);
+    assert_eq!(priority_weights["merge"], 4);
+}
+
+#[test]
+fn ics_events_are_holidays() {
+    let ics = "BEGIN:VCALENDAR\r
+VERSION:2.0\r
+BEGIN:VEVENT\r
+SUMMARY:Independence Day\r
+DTSTART;VALUE=DATE:20240704\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+SUMMARY:Long\r
+  weekend\r
+DTSTART;VALUE=DATE:20241228\r
+DTEND;VALUE=DATE:20250102\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+    let holidays: Vec<String> = calendar::parse_ics(ics)
+        .unwrap()
+        .into_iter()
+        .map(|date| date.to_string())
+        .collect();
+    assert_eq!(
+        holidays,
+        vec![
+            "2024-07-04",
+            "2024-12-28",
+            "2024-12-29",
+            "2024-12-30",
+            "2024-12-31",
+            "2025-01-01",
+        ]
+    );
 }
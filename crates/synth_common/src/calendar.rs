@@ -0,0 +1,86 @@
+use super::config::CalendarSettings;
+use chrono::{Duration, NaiveDate};
+use std::collections::BTreeSet;
+
+/// Format of the holiday dates of a calendar
+pub const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Parse a `YYYY-MM-DD` holiday
+pub fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, DATE_FORMAT)
+        .map_err(|_| format!("Invalid holiday '{}'! Use YYYY-MM-DD", date))
+}
+
+/// Collect the holidays of a calendar from its inline dates and its ICS file,
+/// as sorted `YYYY-MM-DD` dates
+pub fn load_holidays(settings: &CalendarSettings) -> Result<Vec<String>, String> {
+    let mut holidays = settings
+        .holidays
+        .iter()
+        .map(|date| parse_date(date))
+        .collect::<Result<BTreeSet<NaiveDate>, String>>()?;
+    if let Some(ics) = &settings.ics {
+        let contents = std::fs::read_to_string(ics)
+            .map_err(|e| format!("Failed to read ICS file '{}': {}", ics, e))?;
+        holidays.extend(parse_ics(&contents)?);
+    }
+    Ok(holidays
+        .into_iter()
+        .map(|date| date.format(DATE_FORMAT).to_string())
+        .collect())
+}
+
+/// Read the days covered by every event in an ICS calendar.
+///
+/// An event covers every day from its `DTSTART` up to, but not including, its
+/// `DTEND`, or just the day it starts on. Recurrence rules aren't expanded, so
+/// each holiday has to be its own event, as in exchange holiday calendars.
+pub fn parse_ics(contents: &str) -> Result<BTreeSet<NaiveDate>, String> {
+    // Long lines are folded onto following lines that start with whitespace
+    let mut lines: Vec<String> = Vec::new();
+    for line in contents.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(folded), Some(last)) => last.push_str(folded),
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut dates = BTreeSet::new();
+    let mut event: Option<(Option<NaiveDate>, Option<NaiveDate>)> = None;
+    for line in &lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        // Drop parameters such as `;VALUE=DATE` from the property name
+        let name = name.split(';').next().unwrap_or_default().to_uppercase();
+        match (name.as_str(), value.trim(), event.as_mut()) {
+            ("BEGIN", "VEVENT", _) => event = Some((None, None)),
+            ("DTSTART", value, Some((start, _))) => *start = Some(parse_ics_date(value)?),
+            ("DTEND", value, Some((_, end))) => *end = Some(parse_ics_date(value)?),
+            ("END", "VEVENT", Some(_)) => {
+                let Some((Some(start), end)) = event.take() else {
+                    return Err("An ICS event has no DTSTART!".to_string());
+                };
+                let end = end
+                    .filter(|end| *end > start)
+                    .unwrap_or(start + Duration::days(1));
+                let mut date = start;
+                while date < end {
+                    dates.insert(date);
+                    date += Duration::days(1);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(dates)
+}
+
+/// Parse the date of an ICS `DATE` or `DATE-TIME` value, e.g. `20240101` or
+/// `20240101T093000Z`
+fn parse_ics_date(value: &str) -> Result<NaiveDate, String> {
+    value
+        .get(..8)
+        .and_then(|date| NaiveDate::parse_from_str(date, "%Y%m%d").ok())
+        .ok_or_else(|| format!("Invalid ICS date '{}'!", value))
+}
@@ -1,5 +1,7 @@
+pub use config::ConfigError;
 use config::{Config, Environment, File};
 use serde::Deserialize;
+use std::collections::HashMap;
 
 /// Trait used to build URLs for various resources
 pub trait BuildUrl {
@@ -11,6 +13,18 @@ pub struct Settings {
     pub server: ServerSettings,
     pub pipelines: PipelineSettings,
     pub database: DatabaseSettings,
+    #[serde(default)]
+    pub scheduler: SchedulerSettings,
+    #[serde(default)]
+    pub executor: ExecutorSettings,
+    #[serde(default)]
+    pub worker: WorkerSettings,
+    /// Slots of every named pool, registered when the webserver starts
+    #[serde(default)]
+    pub pools: HashMap<String, u32>,
+    /// Business calendars by name, registered when the webserver starts
+    #[serde(default)]
+    pub calendars: HashMap<String, CalendarSettings>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -28,6 +42,115 @@ pub struct PipelineSettings {
     pub dirs: Vec<String>,
 }
 
+/// Seconds that the scheduler sleeps between ticks
+pub const SCHEDULER_INTERVAL: u64 = 5;
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct SchedulerSettings {
+    /// Maximum number of backfill runs that may execute at the same time
+    pub max_active_backfill_runs: u32,
+    /// Seconds that the leader stays in charge without renewing its lease,
+    /// after which a standby scheduler takes over
+    pub leader_lease_duration: u64,
+}
+impl Default for SchedulerSettings {
+    fn default() -> Self {
+        Self {
+            max_active_backfill_runs: 4,
+            leader_lease_duration: 30,
+        }
+    }
+}
+impl SchedulerSettings {
+    /// Check that the leader can renew its lease every tick before it expires
+    fn validate(&self) -> Result<(), config::ConfigError> {
+        if self.leader_lease_duration <= SCHEDULER_INTERVAL {
+            return Err(config::ConfigError::Message(format!(
+                "The scheduler leader_lease_duration ({}) must be longer than its {} second tick!",
+                self.leader_lease_duration, SCHEDULER_INTERVAL
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct ExecutorSettings {
+    /// Maximum number of Tasks that a single executor runs at the same time
+    pub max_concurrent_tasks: usize,
+    /// Seconds that a claimed TaskInstance stays leased to its executor
+    /// without being renewed
+    pub lease_duration: u64,
+}
+impl Default for ExecutorSettings {
+    fn default() -> Self {
+        Self {
+            max_concurrent_tasks: 256,
+            lease_duration: 30,
+        }
+    }
+}
+impl ExecutorSettings {
+    /// Check that the executor can run Tasks at all
+    fn validate(&self) -> Result<(), config::ConfigError> {
+        if self.max_concurrent_tasks == 0 {
+            return Err(config::ConfigError::Message(
+                "The executor max_concurrent_tasks must be at least 1!".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct WorkerSettings {
+    /// Maximum number of Tasks that a single worker runs at the same time
+    pub max_concurrent_tasks: usize,
+    /// Seconds between the heartbeats that keep a worker's leases alive
+    pub heartbeat_interval: u64,
+    /// Seconds that a worker's leases last without a heartbeat
+    pub lease_duration: u64,
+}
+impl Default for WorkerSettings {
+    fn default() -> Self {
+        Self {
+            max_concurrent_tasks: 4,
+            heartbeat_interval: 10,
+            lease_duration: 30,
+        }
+    }
+}
+impl WorkerSettings {
+    /// Check that the worker can run Tasks at all, and that heartbeats are
+    /// sent before the leases they renew expire
+    fn validate(&self) -> Result<(), config::ConfigError> {
+        if self.max_concurrent_tasks == 0 {
+            return Err(config::ConfigError::Message(
+                "The worker max_concurrent_tasks must be at least 1!".to_string(),
+            ));
+        }
+        if self.heartbeat_interval >= self.lease_duration {
+            return Err(config::ConfigError::Message(format!(
+                "The worker heartbeat_interval ({}) must be shorter than its lease_duration ({})!",
+                self.heartbeat_interval, self.lease_duration
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct CalendarSettings {
+    /// Holidays as `YYYY-MM-DD` dates
+    pub holidays: Vec<String>,
+    /// Path to an ICS file whose events are also holidays
+    pub ics: Option<String>,
+}
+
 #[derive(Deserialize, Debug)]
 pub struct ServerSettings {
     pub scheme: String,
@@ -41,9 +164,19 @@ impl BuildUrl for ServerSettings {
 }
 
 pub fn load_config(filepath: &str) -> Result<Settings, config::ConfigError> {
-    Config::builder()
+    let settings = Config::builder()
         .add_source(File::with_name(filepath))
         .add_source(Environment::default().prefix("SYNTH"))
         .build()?
-        .try_deserialize::<Settings>()
+        .try_deserialize::<Settings>()?;
+    settings.scheduler.validate()?;
+    settings.executor.validate()?;
+    settings.worker.validate()?;
+    if let Some((id, _)) = settings.pools.iter().find(|(_, &slots)| slots == 0) {
+        return Err(config::ConfigError::Message(format!(
+            "Pool '{}' needs at least 1 slot!",
+            id
+        )));
+    }
+    Ok(settings)
 }
@@ -0,0 +1,207 @@
+use super::models::{Task, WeightRule};
+use std::collections::{HashMap, HashSet};
+
+/// Find a dependency cycle within a Pipeline's Tasks.
+///
+/// Returns the IDs of the Tasks that form the cycle, starting and ending
+/// with the same Task, or `None` if the Tasks form a valid DAG.
+/// Dependencies on Tasks that aren't in the list are ignored.
+pub fn find_cycle(tasks: &[Task]) -> Option<Vec<String>> {
+    let graph: HashMap<&str, &Vec<String>> = tasks
+        .iter()
+        .map(|task| (task.id.as_str(), &task.depends_on))
+        .collect();
+
+    let mut visited: HashSet<&str> = HashSet::new();
+    for task in tasks {
+        let mut path: Vec<&str> = Vec::new();
+        if let Some(cycle) = visit(&task.id, &graph, &mut visited, &mut path) {
+            return Some(cycle);
+        }
+    }
+    None
+}
+
+/// Depth-first search that tracks the current path to detect back-edges
+fn visit<'a>(
+    task_id: &'a str,
+    graph: &HashMap<&'a str, &'a Vec<String>>,
+    visited: &mut HashSet<&'a str>,
+    path: &mut Vec<&'a str>,
+) -> Option<Vec<String>> {
+    if let Some(position) = path.iter().position(|id| *id == task_id) {
+        let mut cycle: Vec<String> = path[position..].iter().map(|id| id.to_string()).collect();
+        cycle.push(task_id.to_string());
+        return Some(cycle);
+    }
+    if !visited.insert(task_id) {
+        return None;
+    }
+
+    let upstreams = graph.get(task_id)?;
+    path.push(task_id);
+    for upstream in upstreams.iter() {
+        if graph.contains_key(upstream.as_str()) {
+            if let Some(cycle) = visit(upstream, graph, visited, path) {
+                return Some(cycle);
+            }
+        }
+    }
+    path.pop();
+    None
+}
+
+/// Find dependencies that reference Tasks outside of the provided list.
+///
+/// Returns `(task_id, missing_upstream_id)` pairs.
+pub fn find_missing_dependencies(tasks: &[Task]) -> Vec<(String, String)> {
+    let task_ids: HashSet<&str> = tasks.iter().map(|task| task.id.as_str()).collect();
+    tasks
+        .iter()
+        .flat_map(|task| {
+            task.depends_on
+                .iter()
+                .filter(|upstream| !task_ids.contains(upstream.as_str()))
+                .map(|upstream| (task.id.clone(), upstream.clone()))
+        })
+        .collect()
+}
+
+/// Find every Task that directly or transitively depends on a Task.
+///
+/// Returns the downstream Task IDs in the order of the provided list,
+/// excluding the Task itself.
+pub fn find_downstream_tasks(tasks: &[Task], task_id: &str) -> Vec<String> {
+    let mut downstream: HashSet<&str> = HashSet::from([task_id]);
+    // Keep sweeping until no more Tasks are found, since the list may be in any order
+    loop {
+        let found: Vec<&str> = tasks
+            .iter()
+            .filter(|task| !downstream.contains(task.id.as_str()))
+            .filter(|task| {
+                task.depends_on
+                    .iter()
+                    .any(|upstream| downstream.contains(upstream.as_str()))
+            })
+            .map(|task| task.id.as_str())
+            .collect();
+        if found.is_empty() {
+            break;
+        }
+        downstream.extend(found);
+    }
+
+    tasks
+        .iter()
+        .filter(|task| task.id != task_id && downstream.contains(task.id.as_str()))
+        .map(|task| task.id.clone())
+        .collect()
+}
+
+/// Order the positions of Tasks so that every Task comes after the Tasks it
+/// depends on, given the positions of each Task's upstreams.
+///
+/// Tasks that are part of a dependency cycle are left out.
+fn topological_order(upstreams: &[Vec<usize>]) -> Vec<usize> {
+    let mut downstreams: Vec<Vec<usize>> = vec![Vec::new(); upstreams.len()];
+    let mut waiting_on: Vec<usize> = vec![0; upstreams.len()];
+    for (position, task_upstreams) in upstreams.iter().enumerate() {
+        for &upstream in task_upstreams {
+            downstreams[upstream].push(position);
+            waiting_on[position] += 1;
+        }
+    }
+
+    let mut order: Vec<usize> = (0..upstreams.len())
+        .filter(|&position| waiting_on[position] == 0)
+        .collect();
+    let mut next = 0;
+    while let Some(&position) = order.get(next) {
+        next += 1;
+        for &downstream in &downstreams[position] {
+            waiting_on[downstream] -= 1;
+            if waiting_on[downstream] == 0 {
+                order.push(downstream);
+            }
+        }
+    }
+    order
+}
+
+/// Work out the priority weight of every Task in a Pipeline, combining each
+/// Task's own weight with those of its related Tasks according to its rule.
+///
+/// The related Tasks of every Task are collected in a single pass in
+/// topological order, building on those already collected for its neighbours.
+pub fn priority_weights(tasks: &[Task]) -> HashMap<String, i64> {
+    let positions: HashMap<&str, usize> = tasks
+        .iter()
+        .enumerate()
+        .map(|(position, task)| (task.id.as_str(), position))
+        .collect();
+    let upstreams: Vec<Vec<usize>> = tasks
+        .iter()
+        .map(|task| {
+            task.depends_on
+                .iter()
+                .filter_map(|upstream| positions.get(upstream.as_str()).copied())
+                .collect()
+        })
+        .collect();
+    let order = topological_order(&upstreams);
+
+    // Each Task's transitive upstreams are its upstreams and theirs, and the
+    // other way around for downstreams
+    let mut all_upstreams: Vec<HashSet<usize>> = vec![HashSet::new(); tasks.len()];
+    let mut all_downstreams: Vec<HashSet<usize>> = vec![HashSet::new(); tasks.len()];
+    for &position in &order {
+        for &upstream in &upstreams[position] {
+            let inherited = all_upstreams[upstream].clone();
+            all_upstreams[position].extend(inherited);
+            all_upstreams[position].insert(upstream);
+        }
+    }
+    for &position in order.iter().rev() {
+        for &upstream in &upstreams[position] {
+            let inherited = all_downstreams[position].clone();
+            all_downstreams[upstream].extend(inherited);
+            all_downstreams[upstream].insert(position);
+        }
+    }
+
+    let weight_of = |related: &HashSet<usize>| -> i64 {
+        related
+            .iter()
+            .map(|&position| i64::from(tasks[position].priority_weight))
+            .sum()
+    };
+    tasks
+        .iter()
+        .enumerate()
+        .map(|(position, task)| {
+            let related_weight = match task.weight_rule {
+                WeightRule::Downstream => weight_of(&all_downstreams[position]),
+                WeightRule::Upstream => weight_of(&all_upstreams[position]),
+                WeightRule::Absolute => 0,
+            };
+            (
+                task.id.clone(),
+                i64::from(task.priority_weight) + related_weight,
+            )
+        })
+        .collect()
+}
+
+/// Validate that a Pipeline's Tasks form a DAG, returning a readable error otherwise.
+pub fn validate(tasks: &[Task]) -> Result<(), String> {
+    if let Some((task_id, upstream)) = find_missing_dependencies(tasks).first() {
+        return Err(format!(
+            "Task '{}' depends on unknown Task '{}'!",
+            task_id, upstream
+        ));
+    }
+    match find_cycle(tasks) {
+        Some(cycle) => Err(format!("Dependency cycle detected: {}", cycle.join(" -> "))),
+        None => Ok(()),
+    }
+}
@@ -1,5 +1,8 @@
+pub mod calendar;
 pub mod config;
+pub mod dag;
 pub mod database;
 pub mod models;
 pub mod queries;
+pub mod schedule;
 pub mod telemetry;
@@ -0,0 +1,14 @@
+------------------------------------------------------
+-- Create the Task Dependencies table and seed data --
+------------------------------------------------------
+CREATE TABLE IF NOT EXISTS task_dependencies (
+    task_id TEXT NOT NULL
+    , depends_on TEXT NOT NULL
+    , PRIMARY KEY (task_id, depends_on)
+);
+-- Seed Task Dependencies data
+INSERT INTO task_dependencies
+VALUES(
+        'task2',
+        'task1'
+    );
@@ -0,0 +1,6 @@
+----------------------------------------------------
+-- Persist the schedule state of each Pipeline --
+----------------------------------------------------
+-- Both are RFC 3339 timestamps, NULL until the scheduler first sees the Pipeline
+ALTER TABLE pipelines ADD COLUMN next_run_at TEXT;
+ALTER TABLE pipelines ADD COLUMN last_run_at TEXT;
@@ -0,0 +1,17 @@
+-----------------------------------------------------
+-- Allow Pipelines to catch up on missed schedules --
+-----------------------------------------------------
+ALTER TABLE pipelines ADD COLUMN catchup BOOLEAN NOT NULL DEFAULT FALSE;
+
+-----------------------------------
+-- Create the PipelineRuns table --
+-----------------------------------
+CREATE TABLE IF NOT EXISTS pipeline_runs (
+    id TEXT NOT NULL PRIMARY KEY
+    , pipeline_id TEXT NOT NULL
+    , scheduled_time TEXT NOT NULL
+    , trigger TEXT NOT NULL
+    , status TEXT NOT NULL
+    , created_at TEXT NOT NULL
+    , UNIQUE (pipeline_id, scheduled_time)
+);
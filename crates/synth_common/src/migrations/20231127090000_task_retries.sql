@@ -0,0 +1,11 @@
+---------------------------------
+-- Allow Tasks to be retried --
+---------------------------------
+ALTER TABLE tasks ADD COLUMN retries INTEGER NOT NULL DEFAULT 0;
+-- Seconds to wait before the first retry
+ALTER TABLE tasks ADD COLUMN retry_delay INTEGER NOT NULL DEFAULT 0;
+-- One of 'fixed' or 'exponential'
+ALTER TABLE tasks ADD COLUMN retry_backoff TEXT NOT NULL DEFAULT 'fixed';
+
+-- Every attempt of a Task is recorded as its own TaskInstance
+ALTER TABLE task_instances ADD COLUMN attempt INTEGER NOT NULL DEFAULT 1;
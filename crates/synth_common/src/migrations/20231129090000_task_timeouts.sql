@@ -0,0 +1,6 @@
+------------------------------------------
+-- Allow Tasks to time out, in seconds --
+------------------------------------------
+ALTER TABLE tasks ADD COLUMN timeout INTEGER;
+-- Used by any of the Pipeline's Tasks that don't set their own timeout
+ALTER TABLE pipelines ADD COLUMN default_timeout INTEGER;
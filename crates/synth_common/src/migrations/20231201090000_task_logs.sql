@@ -0,0 +1,15 @@
+--------------------------------------------
+-- Store TaskInstance output line by line --
+--------------------------------------------
+CREATE TABLE IF NOT EXISTS task_logs (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT
+    , task_instance_id TEXT NOT NULL
+    , timestamp TEXT NOT NULL
+    -- Either 'stdout' or 'stderr'
+    , stream TEXT NOT NULL
+    , line TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS task_logs_task_instance_id ON task_logs (task_instance_id);
+
+-- Logs used to be stored as a debug-printed byte vector and aren't worth keeping
+ALTER TABLE task_instances DROP COLUMN logs;
@@ -0,0 +1,19 @@
+------------------------------------------------------------
+-- Store exit codes separately from TaskInstance statuses --
+------------------------------------------------------------
+-- Unset until the command exits on its own
+ALTER TABLE task_instances ADD COLUMN exit_code INTEGER;
+
+-- Statuses used to be the printed ExitStatus, e.g. 'exit status: 1'
+UPDATE task_instances
+SET
+    exit_code = CAST(substr(status, 14) AS INTEGER)
+    , status = CASE WHEN status = 'exit status: 0' THEN 'success' ELSE 'failed' END
+WHERE status LIKE 'exit status: %';
+
+-- Anything else that isn't a known status, e.g. 'signal: 9 (SIGKILL)', was a failure
+UPDATE task_instances
+SET status = 'failed'
+WHERE status NOT IN (
+    'queued', 'running', 'success', 'failed', 'timed_out', 'skipped', 'cancelled', 'upstream_failed'
+);
@@ -0,0 +1,21 @@
+-----------------------------------------------------------
+-- Track PipelineRuns and the TaskInstances they contain --
+-----------------------------------------------------------
+-- RFC 3339 timestamps of when the run started and finished
+ALTER TABLE pipeline_runs ADD COLUMN execution_start TEXT;
+ALTER TABLE pipeline_runs ADD COLUMN execution_end TEXT;
+
+ALTER TABLE task_instances ADD COLUMN pipeline_run_id TEXT;
+CREATE INDEX IF NOT EXISTS task_instances_pipeline_run_id ON task_instances (pipeline_run_id);
+
+-- Existing TaskInstances store their tick as e.g. '2023-12-01 09:00:00 UTC'
+UPDATE task_instances
+SET pipeline_run_id = (
+    SELECT pipeline_runs.id
+    FROM pipeline_runs
+    WHERE
+        pipeline_runs.pipeline_id = task_instances.pipeline_id
+        AND pipeline_runs.scheduled_time
+        = replace(replace(task_instances.scheduled_time, ' UTC', '+00:00'), ' ', 'T')
+)
+WHERE pipeline_run_id IS NULL;
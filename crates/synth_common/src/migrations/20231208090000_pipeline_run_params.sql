@@ -0,0 +1,5 @@
+--------------------------------------------
+-- Allow PipelineRuns to carry parameters --
+--------------------------------------------
+-- A JSON object of string values, passed to every Task of the run
+ALTER TABLE pipeline_runs ADD COLUMN params TEXT NOT NULL DEFAULT '{}';
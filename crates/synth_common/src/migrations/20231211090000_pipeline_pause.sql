@@ -0,0 +1,5 @@
+----------------------------------
+-- Allow Pipelines to be paused --
+----------------------------------
+-- Paused Pipelines aren't scheduled and their queued runs aren't started
+ALTER TABLE pipelines ADD COLUMN is_paused BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,17 @@
+-------------------------------------------------------------
+-- Queue TaskInstances for executors to claim with a lease --
+-------------------------------------------------------------
+-- Attempt number after which a failing Task is no longer retried
+ALTER TABLE task_instances ADD COLUMN last_attempt INTEGER NOT NULL DEFAULT 1;
+
+-- Queued retries can't be claimed before their retry delay has passed
+ALTER TABLE task_instances ADD COLUMN run_after TEXT;
+
+-- The executor running a TaskInstance, and until when it holds it
+ALTER TABLE task_instances ADD COLUMN lease_owner TEXT;
+ALTER TABLE task_instances ADD COLUMN lease_expires_at TEXT;
+
+-- Existing TaskInstances already ran as often as they're going to
+UPDATE task_instances SET last_attempt = attempt;
+
+CREATE INDEX task_instances_status_idx ON task_instances (status);
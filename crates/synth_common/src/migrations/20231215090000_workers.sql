@@ -0,0 +1,12 @@
+------------------------------------------------
+-- Remote workers that run Tasks over the API --
+------------------------------------------------
+CREATE TABLE IF NOT EXISTS workers (
+    id TEXT PRIMARY KEY NOT NULL
+    , hostname TEXT NOT NULL
+    , registered_at TEXT NOT NULL
+    -- Workers renew the leases on their TaskInstances with every heartbeat
+    , last_heartbeat_at TEXT NOT NULL
+);
+
+CREATE INDEX task_instances_lease_owner_idx ON task_instances (lease_owner);
@@ -0,0 +1,5 @@
+-------------------------------------------------
+-- Record a heartbeat per running TaskInstance --
+-------------------------------------------------
+-- Set whenever the executor or worker running a TaskInstance renews its lease
+ALTER TABLE task_instances ADD COLUMN last_heartbeat_at TEXT;
@@ -0,0 +1,12 @@
+------------------------------------------------------------
+-- Leases that elect a single leader among the schedulers --
+------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS leader_leases (
+    name TEXT PRIMARY KEY NOT NULL
+    , holder TEXT NOT NULL
+    , acquired_at TEXT NOT NULL
+    -- The holder renews the lease with every heartbeat, anyone may take it
+    -- over once it expires
+    , last_heartbeat_at TEXT NOT NULL
+    , expires_at TEXT NOT NULL
+);
@@ -0,0 +1,4 @@
+--------------------------------------------------------------------------
+-- TaskInstances have random IDs, each attempt is unique within its run --
+--------------------------------------------------------------------------
+CREATE UNIQUE INDEX task_instances_attempt_idx ON task_instances (pipeline_run_id, task_id, attempt);
@@ -0,0 +1,8 @@
+----------------------------------------------------------
+-- Limit how many runs of a Pipeline are active at once --
+----------------------------------------------------------
+-- Unset means there is no limit
+ALTER TABLE pipelines ADD COLUMN max_active_runs INTEGER;
+-- What happens to a scheduled run once the limit is reached, one of `queue`,
+-- `skip` or `cancel_previous`
+ALTER TABLE pipelines ADD COLUMN overlap_policy TEXT NOT NULL DEFAULT 'queue';
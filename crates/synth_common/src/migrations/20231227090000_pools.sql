@@ -0,0 +1,11 @@
+-------------------------------------------------------------------------
+-- Named pools that limit how many Tasks run at once, across Pipelines --
+-------------------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS pools (
+    id TEXT PRIMARY KEY NOT NULL
+    , slots INTEGER NOT NULL
+);
+
+-- Tasks without a pool aren't limited
+ALTER TABLE tasks ADD COLUMN pool TEXT;
+ALTER TABLE tasks ADD COLUMN pool_slots INTEGER NOT NULL DEFAULT 1;
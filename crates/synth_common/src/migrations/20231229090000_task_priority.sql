@@ -0,0 +1,10 @@
+-----------------------------------------------------------------------
+-- Priority weights that decide which queued Tasks are claimed first --
+-----------------------------------------------------------------------
+ALTER TABLE tasks ADD COLUMN priority_weight INTEGER NOT NULL DEFAULT 1;
+-- How a Task's weight is combined with the weights of related Tasks, one of
+-- `downstream`, `upstream` or `absolute`
+ALTER TABLE tasks ADD COLUMN weight_rule TEXT NOT NULL DEFAULT 'downstream';
+
+-- The combined weight of the Task when the TaskInstance was created
+ALTER TABLE task_instances ADD COLUMN priority_weight INTEGER NOT NULL DEFAULT 1;
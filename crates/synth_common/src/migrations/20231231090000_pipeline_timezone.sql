@@ -0,0 +1,5 @@
+---------------------------------------------------
+-- Evaluate a Pipeline's schedule in a time zone --
+---------------------------------------------------
+-- The IANA name of the time zone, unset for UTC
+ALTER TABLE pipelines ADD COLUMN timezone TEXT;
@@ -0,0 +1,6 @@
+--------------------------------------------
+-- Bound when a Pipeline's schedule fires --
+--------------------------------------------
+-- Inclusive RFC 3339 timestamps or YYYY-MM-DD dates, unset for no bound
+ALTER TABLE pipelines ADD COLUMN start_date TEXT;
+ALTER TABLE pipelines ADD COLUMN end_date TEXT;
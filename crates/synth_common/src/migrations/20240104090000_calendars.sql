@@ -0,0 +1,14 @@
+----------------------------------------------------------------
+-- Business calendars whose holidays Pipeline schedules avoid --
+----------------------------------------------------------------
+CREATE TABLE IF NOT EXISTS calendars (
+    id TEXT PRIMARY KEY NOT NULL
+    -- A JSON array of sorted YYYY-MM-DD dates
+    , holidays TEXT NOT NULL DEFAULT '[]'
+);
+
+-- Pipelines without a calendar run on holidays
+ALTER TABLE pipelines ADD COLUMN calendar TEXT;
+-- What happens to a scheduled run on a holiday, either `skip_on_holiday` or
+-- `next_business_day`
+ALTER TABLE pipelines ADD COLUMN holiday_policy TEXT NOT NULL DEFAULT 'skip_on_holiday';
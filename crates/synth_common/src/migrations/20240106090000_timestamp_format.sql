@@ -0,0 +1,40 @@
+-------------------------------------------------------------------------
+-- Store every timestamp as RFC 3339 in UTC, e.g. 2024-01-06T09:00:00Z --
+-------------------------------------------------------------------------
+-- Timestamps used to be either e.g. '2024-01-06 09:00:00.123 UTC' or
+-- '2024-01-06T09:00:00.123+00:00', which don't sort or compare with each other
+UPDATE task_instances
+SET scheduled_time = strftime('%Y-%m-%dT%H:%M:%SZ', substr(scheduled_time, 1, 19))
+WHERE scheduled_time LIKE '% UTC' OR scheduled_time LIKE '%+00:00';
+
+UPDATE task_instances
+SET created_at = strftime('%Y-%m-%dT%H:%M:%SZ', substr(created_at, 1, 19))
+WHERE created_at LIKE '% UTC' OR created_at LIKE '%+00:00';
+
+UPDATE pipeline_runs
+SET scheduled_time = strftime('%Y-%m-%dT%H:%M:%SZ', substr(scheduled_time, 1, 19))
+WHERE scheduled_time LIKE '% UTC' OR scheduled_time LIKE '%+00:00';
+
+UPDATE pipeline_runs
+SET created_at = strftime('%Y-%m-%dT%H:%M:%SZ', substr(created_at, 1, 19))
+WHERE created_at LIKE '% UTC' OR created_at LIKE '%+00:00';
+
+UPDATE pipeline_runs
+SET execution_start = strftime('%Y-%m-%dT%H:%M:%SZ', substr(execution_start, 1, 19))
+WHERE execution_start LIKE '%+00:00';
+
+UPDATE pipeline_runs
+SET execution_end = strftime('%Y-%m-%dT%H:%M:%SZ', substr(execution_end, 1, 19))
+WHERE execution_end LIKE '%+00:00';
+
+UPDATE pipelines
+SET next_run_at = strftime('%Y-%m-%dT%H:%M:%SZ', substr(next_run_at, 1, 19))
+WHERE next_run_at LIKE '%+00:00';
+
+UPDATE pipelines
+SET last_run_at = strftime('%Y-%m-%dT%H:%M:%SZ', substr(last_run_at, 1, 19))
+WHERE last_run_at LIKE '%+00:00';
+
+UPDATE task_logs
+SET timestamp = strftime('%Y-%m-%dT%H:%M:%SZ', substr(timestamp, 1, 19))
+WHERE timestamp LIKE '%+00:00';
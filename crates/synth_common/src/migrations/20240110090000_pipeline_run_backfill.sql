@@ -0,0 +1,37 @@
+------------------------------------------------------------
+-- Give TaskInstances from before PipelineRuns a run each --
+------------------------------------------------------------
+-- TaskInstances created before PipelineRuns were tracked had no run to link
+-- to, so every tick they ran for gets a run of its own
+INSERT OR IGNORE INTO pipeline_runs (
+    id, pipeline_id, scheduled_time, trigger, status, created_at, execution_start, execution_end
+)
+SELECT
+    lower(hex(randomblob(16)))
+    , pipeline_id
+    , scheduled_time
+    , 'scheduled'
+    , CASE
+        WHEN max(status IN ('queued', 'pending', 'running')) THEN 'running'
+        WHEN min(status IN ('success', 'skipped')) THEN 'success'
+        ELSE 'failed'
+    END
+    , min(created_at)
+    , min(nullif(execution_start, ''))
+    , CASE
+        WHEN max(status IN ('queued', 'pending', 'running')) THEN NULL
+        ELSE max(nullif(execution_end, ''))
+    END
+FROM task_instances
+WHERE pipeline_run_id IS NULL
+GROUP BY pipeline_id, scheduled_time;
+
+UPDATE task_instances
+SET pipeline_run_id = (
+    SELECT pipeline_runs.id
+    FROM pipeline_runs
+    WHERE
+        pipeline_runs.pipeline_id = task_instances.pipeline_id
+        AND pipeline_runs.scheduled_time = task_instances.scheduled_time
+)
+WHERE pipeline_run_id IS NULL;
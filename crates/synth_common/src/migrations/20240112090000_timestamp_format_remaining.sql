@@ -0,0 +1,82 @@
+----------------------------------------------------------------
+-- Store the remaining timestamps as RFC 3339 in UTC, as well --
+----------------------------------------------------------------
+-- Leases, heartbeats and retry times are compared as strings, so every
+-- timestamp has to share the format e.g. 2024-01-06T09:00:00Z. Empty and
+-- NULL timestamps are left as they are.
+
+UPDATE task_instances
+SET scheduled_time = strftime('%Y-%m-%dT%H:%M:%SZ', substr(scheduled_time, 1, 19))
+WHERE scheduled_time != strftime('%Y-%m-%dT%H:%M:%SZ', substr(scheduled_time, 1, 19));
+
+UPDATE task_instances
+SET created_at = strftime('%Y-%m-%dT%H:%M:%SZ', substr(created_at, 1, 19))
+WHERE created_at != strftime('%Y-%m-%dT%H:%M:%SZ', substr(created_at, 1, 19));
+
+UPDATE task_instances
+SET execution_start = strftime('%Y-%m-%dT%H:%M:%SZ', substr(execution_start, 1, 19))
+WHERE execution_start != strftime('%Y-%m-%dT%H:%M:%SZ', substr(execution_start, 1, 19));
+
+UPDATE task_instances
+SET execution_end = strftime('%Y-%m-%dT%H:%M:%SZ', substr(execution_end, 1, 19))
+WHERE execution_end != strftime('%Y-%m-%dT%H:%M:%SZ', substr(execution_end, 1, 19));
+
+UPDATE task_instances
+SET run_after = strftime('%Y-%m-%dT%H:%M:%SZ', substr(run_after, 1, 19))
+WHERE run_after != strftime('%Y-%m-%dT%H:%M:%SZ', substr(run_after, 1, 19));
+
+UPDATE task_instances
+SET lease_expires_at = strftime('%Y-%m-%dT%H:%M:%SZ', substr(lease_expires_at, 1, 19))
+WHERE lease_expires_at != strftime('%Y-%m-%dT%H:%M:%SZ', substr(lease_expires_at, 1, 19));
+
+UPDATE task_instances
+SET last_heartbeat_at = strftime('%Y-%m-%dT%H:%M:%SZ', substr(last_heartbeat_at, 1, 19))
+WHERE last_heartbeat_at != strftime('%Y-%m-%dT%H:%M:%SZ', substr(last_heartbeat_at, 1, 19));
+
+UPDATE pipeline_runs
+SET scheduled_time = strftime('%Y-%m-%dT%H:%M:%SZ', substr(scheduled_time, 1, 19))
+WHERE scheduled_time != strftime('%Y-%m-%dT%H:%M:%SZ', substr(scheduled_time, 1, 19));
+
+UPDATE pipeline_runs
+SET created_at = strftime('%Y-%m-%dT%H:%M:%SZ', substr(created_at, 1, 19))
+WHERE created_at != strftime('%Y-%m-%dT%H:%M:%SZ', substr(created_at, 1, 19));
+
+UPDATE pipeline_runs
+SET execution_start = strftime('%Y-%m-%dT%H:%M:%SZ', substr(execution_start, 1, 19))
+WHERE execution_start != strftime('%Y-%m-%dT%H:%M:%SZ', substr(execution_start, 1, 19));
+
+UPDATE pipeline_runs
+SET execution_end = strftime('%Y-%m-%dT%H:%M:%SZ', substr(execution_end, 1, 19))
+WHERE execution_end != strftime('%Y-%m-%dT%H:%M:%SZ', substr(execution_end, 1, 19));
+
+UPDATE pipelines
+SET next_run_at = strftime('%Y-%m-%dT%H:%M:%SZ', substr(next_run_at, 1, 19))
+WHERE next_run_at != strftime('%Y-%m-%dT%H:%M:%SZ', substr(next_run_at, 1, 19));
+
+UPDATE pipelines
+SET last_run_at = strftime('%Y-%m-%dT%H:%M:%SZ', substr(last_run_at, 1, 19))
+WHERE last_run_at != strftime('%Y-%m-%dT%H:%M:%SZ', substr(last_run_at, 1, 19));
+
+UPDATE task_logs
+SET timestamp = strftime('%Y-%m-%dT%H:%M:%SZ', substr(timestamp, 1, 19))
+WHERE timestamp != strftime('%Y-%m-%dT%H:%M:%SZ', substr(timestamp, 1, 19));
+
+UPDATE workers
+SET registered_at = strftime('%Y-%m-%dT%H:%M:%SZ', substr(registered_at, 1, 19))
+WHERE registered_at != strftime('%Y-%m-%dT%H:%M:%SZ', substr(registered_at, 1, 19));
+
+UPDATE workers
+SET last_heartbeat_at = strftime('%Y-%m-%dT%H:%M:%SZ', substr(last_heartbeat_at, 1, 19))
+WHERE last_heartbeat_at != strftime('%Y-%m-%dT%H:%M:%SZ', substr(last_heartbeat_at, 1, 19));
+
+UPDATE leader_leases
+SET acquired_at = strftime('%Y-%m-%dT%H:%M:%SZ', substr(acquired_at, 1, 19))
+WHERE acquired_at != strftime('%Y-%m-%dT%H:%M:%SZ', substr(acquired_at, 1, 19));
+
+UPDATE leader_leases
+SET last_heartbeat_at = strftime('%Y-%m-%dT%H:%M:%SZ', substr(last_heartbeat_at, 1, 19))
+WHERE last_heartbeat_at != strftime('%Y-%m-%dT%H:%M:%SZ', substr(last_heartbeat_at, 1, 19));
+
+UPDATE leader_leases
+SET expires_at = strftime('%Y-%m-%dT%H:%M:%SZ', substr(expires_at, 1, 19))
+WHERE expires_at != strftime('%Y-%m-%dT%H:%M:%SZ', substr(expires_at, 1, 19));
@@ -0,0 +1,34 @@
+------------------------------------------------------------------
+-- Keep manual PipelineRuns from taking the ticks of a schedule --
+------------------------------------------------------------------
+-- A manual run for e.g. the current time used to take the place of a
+-- scheduled run for the same tick. Scheduled and backfilled runs are now
+-- unique per tick apart from manual ones, which are unique amongst themselves.
+CREATE TABLE pipeline_runs_new (
+    id TEXT NOT NULL PRIMARY KEY
+    , pipeline_id TEXT NOT NULL
+    , scheduled_time TEXT NOT NULL
+    , trigger TEXT NOT NULL
+    , status TEXT NOT NULL
+    , created_at TEXT NOT NULL
+    -- RFC 3339 timestamps of when the run started and finished
+    , execution_start TEXT
+    , execution_end TEXT
+    -- A JSON object of string values, passed to every Task of the run
+    , params TEXT NOT NULL DEFAULT '{}'
+);
+
+INSERT INTO pipeline_runs_new (
+    id, pipeline_id, scheduled_time, trigger, status, created_at, execution_start, execution_end, params
+)
+SELECT
+    id, pipeline_id, scheduled_time, trigger, status, created_at, execution_start, execution_end, params
+FROM pipeline_runs;
+
+DROP TABLE pipeline_runs;
+ALTER TABLE pipeline_runs_new RENAME TO pipeline_runs;
+
+CREATE UNIQUE INDEX pipeline_runs_tick_idx ON pipeline_runs (pipeline_id, scheduled_time)
+WHERE trigger != 'manual';
+CREATE UNIQUE INDEX pipeline_runs_manual_tick_idx ON pipeline_runs (pipeline_id, scheduled_time)
+WHERE trigger = 'manual';
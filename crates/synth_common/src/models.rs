@@ -1,16 +1,494 @@
 use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct Task {
     pub id: String,
     pub pipeline_id: String,
     pub command: String,
+    /// IDs of the Tasks within the same Pipeline that must succeed first
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    /// How many times a failed Task is retried
+    #[serde(default)]
+    pub retries: u32,
+    /// Seconds to wait before the first retry
+    #[serde(default)]
+    pub retry_delay: u32,
+    /// How the delay grows between retries
+    #[serde(default)]
+    pub retry_backoff: RetryBackoff,
+    /// Seconds an attempt may run before it is killed, overriding the Pipeline's default
+    #[serde(default)]
+    pub timeout: Option<u32>,
+    /// Name of the pool that limits how many Tasks like this one run at once
+    #[serde(default)]
+    pub pool: Option<String>,
+    /// How many of the pool's slots a running attempt takes up
+    #[serde(default = "default_pool_slots")]
+    pub pool_slots: u32,
+    /// How urgently the Task runs compared to other queued Tasks
+    #[serde(default = "default_priority_weight")]
+    pub priority_weight: u32,
+    /// How the priority weight is combined with those of related Tasks
+    #[serde(default)]
+    pub weight_rule: WeightRule,
+}
+impl Default for Task {
+    fn default() -> Self {
+        Self {
+            id: String::new(),
+            pipeline_id: String::new(),
+            command: String::new(),
+            depends_on: Vec::new(),
+            retries: 0,
+            retry_delay: 0,
+            retry_backoff: RetryBackoff::default(),
+            timeout: None,
+            pool: None,
+            pool_slots: default_pool_slots(),
+            priority_weight: default_priority_weight(),
+            weight_rule: WeightRule::default(),
+        }
+    }
+}
+
+/// Pool slots that a Task takes up unless it says otherwise
+pub fn default_pool_slots() -> u32 {
+    1
+}
+
+/// Priority weight of a Task unless it says otherwise
+pub fn default_priority_weight() -> u32 {
+    1
+}
+
+/// How a Task's priority weight is combined with the weights of the Tasks
+/// related to it, so that queued Tasks are claimed in a sensible order
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum WeightRule {
+    /// Add the weights of every Task downstream, favouring Tasks that unblock
+    /// the most work
+    #[default]
+    Downstream,
+    /// Add the weights of every Task upstream, favouring Tasks that finish
+    /// Pipeline Runs that are furthest along
+    Upstream,
+    /// Only use the Task's own weight
+    Absolute,
+}
+impl WeightRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Downstream => "downstream",
+            Self::Upstream => "upstream",
+            Self::Absolute => "absolute",
+        }
+    }
+}
+impl fmt::Display for WeightRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+impl FromStr for WeightRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "downstream" => Ok(Self::Downstream),
+            "upstream" => Ok(Self::Upstream),
+            "absolute" => Ok(Self::Absolute),
+            _ => Err(format!("Unknown weight rule '{}'!", s)),
+        }
+    }
+}
+
+/// Strategy used to space out the retries of a failed Task
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryBackoff {
+    /// Wait `retry_delay` seconds between every attempt
+    #[default]
+    Fixed,
+    /// Double the delay after every attempt, with random jitter
+    Exponential,
+}
+impl RetryBackoff {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Fixed => "fixed",
+            Self::Exponential => "exponential",
+        }
+    }
+}
+impl fmt::Display for RetryBackoff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+impl FromStr for RetryBackoff {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fixed" => Ok(Self::Fixed),
+            "exponential" => Ok(Self::Exponential),
+            _ => Err(format!("Unknown retry backoff '{}'!", s)),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
 pub struct Pipeline {
     pub id: String,
     pub schedule: String,
+    /// IANA name of the time zone that the schedule is evaluated in, e.g.
+    /// `Europe/Berlin`, unset for UTC
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// The first time the schedule may fire, as an RFC 3339 timestamp or a
+    /// `YYYY-MM-DD` date. Interval schedules count from it
+    #[serde(default)]
+    pub start_date: Option<String>,
+    /// The last time the schedule may fire, as an RFC 3339 timestamp or a
+    /// `YYYY-MM-DD` date, which includes the whole day
+    #[serde(default)]
+    pub end_date: Option<String>,
+    /// The next time the Pipeline is due to run, managed by the scheduler
+    #[serde(default)]
+    pub next_run_at: Option<String>,
+    /// The scheduled time of the Pipeline's most recent run
+    #[serde(default)]
+    pub last_run_at: Option<String>,
+    /// Whether schedule ticks missed while the scheduler was down are run
+    #[serde(default)]
+    pub catchup: bool,
+    /// Seconds a Task attempt may run before it is killed, unless the Task sets its own
+    #[serde(default)]
+    pub default_timeout: Option<i64>,
+    /// Whether the scheduler is holding off on the Pipeline
+    #[serde(default)]
+    pub is_paused: bool,
+    /// How many runs of the Pipeline may be running at once, unset for no limit
+    #[serde(default)]
+    pub max_active_runs: Option<i64>,
+    /// What happens to a scheduled run when `max_active_runs` is reached
+    #[serde(default)]
+    pub overlap_policy: OverlapPolicy,
+    /// Name of the business calendar whose holidays the schedule avoids
+    #[serde(default)]
+    pub calendar: Option<String>,
+    /// What happens to a scheduled run that falls on a holiday of the calendar
+    #[serde(default)]
+    pub holiday_policy: HolidayPolicy,
+}
+
+/// What the scheduler does with a new scheduled run of a Pipeline that already
+/// has as many active runs as it may have
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone, Copy, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum OverlapPolicy {
+    /// Keep the run queued until an active run finishes
+    #[default]
+    Queue,
+    /// Drop the run
+    Skip,
+    /// Cancel the oldest active runs to make room for it
+    CancelPrevious,
+}
+impl OverlapPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Queue => "queue",
+            Self::Skip => "skip",
+            Self::CancelPrevious => "cancel_previous",
+        }
+    }
+}
+impl fmt::Display for OverlapPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// What the scheduler does with a scheduled run of a Pipeline that falls on a
+/// holiday of the Pipeline's calendar
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone, Copy, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum HolidayPolicy {
+    /// Drop the run
+    #[default]
+    SkipOnHoliday,
+    /// Run at the same time on the next weekday that isn't a holiday
+    NextBusinessDay,
+}
+impl HolidayPolicy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::SkipOnHoliday => "skip_on_holiday",
+            Self::NextBusinessDay => "next_business_day",
+        }
+    }
+}
+impl fmt::Display for HolidayPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// What created a PipelineRun
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone, Copy, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum RunTrigger {
+    /// The scheduler, when the Pipeline's schedule came due
+    #[default]
+    Scheduled,
+    /// A backfill, or the scheduler catching up on missed ticks
+    Backfill,
+    /// A user, through the API, CLI or UI
+    Manual,
+}
+impl RunTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Scheduled => "scheduled",
+            Self::Backfill => "backfill",
+            Self::Manual => "manual",
+        }
+    }
+}
+impl fmt::Display for RunTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Where a PipelineRun is in its lifecycle
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone, Copy, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum PipelineRunStatus {
+    /// Waiting for the scheduler to start it
+    #[default]
+    Queued,
+    Running,
+    /// Every Task of the run succeeded
+    Success,
+    /// A Task of the run failed or was stopped
+    Failed,
+    /// The run was stopped on request, or to make room for a newer run
+    Cancelled,
+    /// The run was dropped by the Pipeline's overlap policy
+    Skipped,
+}
+impl PipelineRunStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Success => "success",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+            Self::Skipped => "skipped",
+        }
+    }
+
+    /// Whether the PipelineRun has reached a final status
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, Self::Queued | Self::Running)
+    }
+
+    /// Whether a PipelineRun may move from this status to another. A finished
+    /// run is queued again when its TaskInstances are cleared.
+    pub fn can_transition_to(&self, next: PipelineRunStatus) -> bool {
+        match self {
+            Self::Queued => matches!(next, Self::Running | Self::Skipped | Self::Cancelled),
+            Self::Running => matches!(next, Self::Success | Self::Failed | Self::Cancelled),
+            _ => next == Self::Queued,
+        }
+    }
+}
+impl fmt::Display for PipelineRunStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct PipelineRun {
+    pub id: String,
+    pub pipeline_id: String,
+    /// The RFC 3339 schedule tick that this run is for
+    pub scheduled_time: String,
+    pub trigger: RunTrigger,
+    pub status: PipelineRunStatus,
+    pub created_at: String,
+    /// RFC 3339 timestamp of when the run started
+    pub execution_start: Option<String>,
+    /// RFC 3339 timestamp of when the run finished
+    pub execution_end: Option<String>,
+    /// Values passed to every Task of the run as `SYNTH_PARAM_<name>` environment variables
+    pub params: Json<HashMap<String, String>>,
+}
+
+/// Request body used to backfill a Pipeline over a range of time
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct Backfill {
+    /// RFC 3339 timestamp of the start of the range, inclusive
+    pub start: String,
+    /// RFC 3339 timestamp of the end of the range, inclusive
+    pub end: String,
+}
+
+/// Request body used to manually trigger a run of a Pipeline
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct Trigger {
+    /// RFC 3339 timestamp that the run is for, defaulting to now
+    #[serde(default)]
+    pub logical_date: Option<String>,
+    /// Values passed to every Task of the run
+    #[serde(default)]
+    pub params: HashMap<String, String>,
+}
+impl Trigger {
+    /// Check that every parameter name can be used in an environment variable
+    pub fn validate_params(&self) -> Result<(), String> {
+        for name in self.params.keys() {
+            let valid =
+                !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                return Err(format!(
+                    "Parameter '{}' may only contain letters, digits and underscores!",
+                    name
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Request body used to clear a TaskInstance so that its Task runs again
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct Clear {
+    /// Also re-run every Task downstream of the cleared Task
+    #[serde(default)]
+    pub downstream: bool,
+}
+
+/// Where a TaskInstance is in its lifecycle
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone, Copy, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(rename_all = "snake_case")]
+pub enum TaskStatus {
+    /// Waiting for an executor to claim it
+    #[default]
+    Queued,
+    /// Waiting for the Tasks it depends on, before the scheduler queues it
+    Pending,
+    Running,
+    /// The command exited with a zero exit code
+    Success,
+    /// The command exited with a non-zero exit code or was killed by a signal
+    Failed,
+    /// The command ran past its timeout and was killed
+    TimedOut,
+    /// The Task was deliberately not run
+    Skipped,
+    /// The Task was stopped on request
+    Cancelled,
+    /// The executor or worker running the Task stopped sending heartbeats
+    Zombie,
+    /// The Task can't run because a Task it depends on didn't succeed
+    UpstreamFailed,
+}
+impl TaskStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Success => "success",
+            Self::Failed => "failed",
+            Self::TimedOut => "timed_out",
+            Self::Skipped => "skipped",
+            Self::Cancelled => "cancelled",
+            Self::Zombie => "zombie",
+            Self::UpstreamFailed => "upstream_failed",
+        }
+    }
+
+    /// Whether the TaskInstance has reached a final status
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, Self::Queued | Self::Pending | Self::Running)
+    }
+
+    /// Whether a TaskInstance may move from this status to another. A queued
+    /// TaskInstance fails without running if its pool can never fit it.
+    pub fn can_transition_to(&self, next: TaskStatus) -> bool {
+        match self {
+            Self::Queued => matches!(
+                next,
+                Self::Running
+                    | Self::Failed
+                    | Self::Skipped
+                    | Self::Cancelled
+                    | Self::UpstreamFailed
+            ),
+            Self::Pending => matches!(
+                next,
+                Self::Queued | Self::Skipped | Self::Cancelled | Self::UpstreamFailed
+            ),
+            Self::Running => matches!(
+                next,
+                Self::Success | Self::Failed | Self::TimedOut | Self::Cancelled | Self::Zombie
+            ),
+            _ => false,
+        }
+    }
+
+    /// Move to another status, failing if the transition isn't allowed
+    pub fn transition_to(self, next: TaskStatus) -> Result<TaskStatus, String> {
+        if self.can_transition_to(next) {
+            Ok(next)
+        } else {
+            Err(format!(
+                "Task instance can't move from '{}' to '{}'!",
+                self, next
+            ))
+        }
+    }
+}
+impl fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+impl FromStr for TaskStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "queued" => Ok(Self::Queued),
+            "pending" => Ok(Self::Pending),
+            "running" => Ok(Self::Running),
+            "success" => Ok(Self::Success),
+            "failed" => Ok(Self::Failed),
+            "timed_out" => Ok(Self::TimedOut),
+            "skipped" => Ok(Self::Skipped),
+            "cancelled" => Ok(Self::Cancelled),
+            "zombie" => Ok(Self::Zombie),
+            "upstream_failed" => Ok(Self::UpstreamFailed),
+            _ => Err(format!("Unknown task status '{}'!", s)),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
@@ -18,10 +496,116 @@ pub struct TaskInstance {
     pub id: String,
     pub task_id: String,
     pub pipeline_id: String,
+    /// The PipelineRun that this TaskInstance is part of
+    pub pipeline_run_id: Option<String>,
     pub scheduled_time: String,
     pub execution_start: String,
     pub execution_end: String,
-    pub status: String,
-    pub logs: String,
+    pub status: TaskStatus,
+    /// Exit code of the command, unset until it exits on its own
+    pub exit_code: Option<i64>,
     pub created_at: String,
+    /// Which try of the Task this is, starting at 1
+    pub attempt: i64,
+    /// Attempt number after which a failing Task is no longer retried
+    pub last_attempt: i64,
+    /// A queued TaskInstance isn't claimed by an executor before this time
+    pub run_after: Option<String>,
+    /// Last time that the executor or worker running the TaskInstance checked in
+    pub last_heartbeat_at: Option<String>,
+    /// Queued TaskInstances with a higher weight are claimed first
+    pub priority_weight: i64,
+}
+
+/// A single line of output from a TaskInstance
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct TaskLog {
+    pub id: i64,
+    pub task_instance_id: String,
+    /// RFC 3339 timestamp of when the line was read
+    pub timestamp: String,
+    /// Either `stdout` or `stderr`
+    pub stream: String,
+    pub line: String,
+}
+impl fmt::Display for TaskLog {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} [{}] {}", self.timestamp, self.stream, self.line)
+    }
+}
+
+/// A remote machine that claims and runs TaskInstances through the API
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct Worker {
+    pub id: String,
+    pub hostname: String,
+    pub registered_at: String,
+    pub last_heartbeat_at: String,
+}
+
+/// Request body used to register a Worker
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct RegisterWorker {
+    pub hostname: String,
+}
+
+/// Request body of a Worker's heartbeat
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct WorkerHeartbeat {
+    /// IDs of the TaskInstances that the Worker is currently running
+    #[serde(default)]
+    pub running: Vec<String>,
+}
+
+/// A claimed TaskInstance along with everything needed to run it
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct TaskAssignment {
+    pub task_instance: TaskInstance,
+    pub command: String,
+    /// Seconds the command may run for, including the Pipeline's default
+    pub timeout: Option<u32>,
+    /// Parameters of the PipelineRun, passed to the command as env vars
+    pub params: HashMap<String, String>,
+}
+
+/// Request body used by a Worker to report how a TaskInstance ended
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct TaskResult {
+    pub status: TaskStatus,
+    /// Exit code of the command, unset if it was killed by a signal
+    pub exit_code: Option<i64>,
+}
+
+/// A lease electing which of several processes, e.g. schedulers, is the leader
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct LeaderLease {
+    pub name: String,
+    /// ID of the process that holds the lease
+    pub holder: String,
+    pub acquired_at: String,
+    pub last_heartbeat_at: String,
+    pub expires_at: String,
+}
+impl LeaderLease {
+    /// Name of the lease held by the scheduler that is actively scheduling
+    pub const SCHEDULER: &'static str = "scheduler";
+}
+
+/// A named business calendar, listing the holidays that schedules can avoid
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct Calendar {
+    pub id: String,
+    /// Sorted `YYYY-MM-DD` dates
+    pub holidays: Json<Vec<String>>,
+}
+
+/// A named limit on how many Tasks run at once, shared by every Pipeline
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Clone)]
+pub struct ResourcePool {
+    pub id: String,
+    /// How many slots running Tasks in the pool may take up in total
+    pub slots: i64,
+    /// Slots taken up by running Tasks, computed when the pool is read
+    #[serde(default)]
+    pub used_slots: i64,
 }
@@ -1,84 +1,661 @@
-use super::models::{Pipeline, Task, TaskInstance};
-use sqlx::{self, Pool, Sqlite};
+use super::models::{
+    Calendar, HolidayPolicy, LeaderLease, OverlapPolicy, Pipeline, PipelineRun, PipelineRunStatus,
+    ResourcePool, RunTrigger, Task, TaskAssignment, TaskInstance, TaskLog, TaskStatus, Worker,
+};
+use sqlx::types::Json;
+use sqlx::{self, Pool, Sqlite, Transaction};
+use std::collections::HashMap;
+use uuid::Uuid;
 
-/// Insert a TaskInstance into the database
+/// Insert a TaskInstance into the database.
+///
+/// An attempt of a Task within a PipelineRun is only ever recorded once, so
+/// inserting it again leaves the existing TaskInstance as it is. Returns
+/// whether the TaskInstance was inserted.
 pub async fn insert_task_instance(
     task_instance: TaskInstance,
     db_pool: &Pool<Sqlite>,
-) -> Result<(), sqlx::Error> {
-    sqlx::query!(
-                "INSERT INTO task_instances (id, task_id, pipeline_id, scheduled_time, execution_start, execution_end, status, logs, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+                "INSERT INTO task_instances (id, task_id, pipeline_id, pipeline_run_id, scheduled_time, execution_start, execution_end, status, exit_code, created_at, attempt, last_attempt, run_after, priority_weight) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT (pipeline_run_id, task_id, attempt) DO NOTHING",
                 task_instance.id,
                 task_instance.task_id,
                 task_instance.pipeline_id,
+                task_instance.pipeline_run_id,
                 task_instance.scheduled_time,
                 task_instance.execution_start,
                 task_instance.execution_end,
                 task_instance.status,
-                task_instance.logs,
+                task_instance.exit_code,
                 task_instance.created_at,
+                task_instance.attempt,
+                task_instance.last_attempt,
+                task_instance.run_after,
+                task_instance.priority_weight,
             )
             .execute(db_pool)
             .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Record the outcome of a running TaskInstance leased to `lease_owner`.
+///
+/// Returns whether the TaskInstance was still running under that lease and
+/// could move to the given status.
+pub async fn finish_task_instance(
+    task_instance_id: &str,
+    lease_owner: &str,
+    status: TaskStatus,
+    exit_code: Option<i64>,
+    execution_end: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<bool, sqlx::Error> {
+    let from_status = TaskStatus::Running;
+    if !from_status.can_transition_to(status) {
+        return Ok(false);
+    }
+    let result = sqlx::query!(
+        "UPDATE task_instances SET status = ?, exit_code = ?, execution_end = ? WHERE id = ? AND status = ? AND lease_owner = ?",
+        status,
+        exit_code,
+        execution_end,
+        task_instance_id,
+        from_status,
+        lease_owner,
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Claim the queued TaskInstance with the highest priority weight that may
+/// run, and start it. Ties go to the oldest TaskInstance.
+///
+/// A TaskInstance whose Task is in a pool may only run once the pool has
+/// enough free slots for it. The claim and the move to running happen in a
+/// single statement, so each TaskInstance is only claimed by one executor and
+/// pools are never oversubscribed. The executor holds a lease on
+/// the TaskInstance until `lease_expires_at`, and has to renew it while running.
+pub async fn claim_task_instance(
+    lease_owner: &str,
+    now: &str,
+    lease_expires_at: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<Option<TaskInstance>, sqlx::Error> {
+    let task_instance = sqlx::query_as!(
+        TaskInstance,
+        r#"UPDATE task_instances SET status = 'running', execution_start = ?, lease_owner = ?, lease_expires_at = ?, last_heartbeat_at = ? WHERE id = (
+            SELECT task_instances.id FROM task_instances
+            LEFT JOIN tasks ON tasks.id = task_instances.task_id AND tasks.pipeline_id = task_instances.pipeline_id
+            LEFT JOIN pools ON pools.id = tasks.pool
+            WHERE task_instances.status = 'queued' AND (task_instances.run_after IS NULL OR task_instances.run_after <= ?)
+                AND (tasks.pool IS NULL OR tasks.pool_slots + (
+                    SELECT COALESCE(SUM(running_tasks.pool_slots), 0) FROM task_instances AS running
+                    JOIN tasks AS running_tasks ON running_tasks.id = running.task_id AND running_tasks.pipeline_id = running.pipeline_id
+                    WHERE running.status = 'running' AND running_tasks.pool = tasks.pool
+                ) <= pools.slots)
+            ORDER BY task_instances.priority_weight DESC, task_instances.created_at LIMIT 1
+        ) AND status = 'queued' RETURNING id, task_id, pipeline_id, pipeline_run_id, scheduled_time, execution_start, execution_end, status AS "status: TaskStatus", exit_code, created_at, attempt, last_attempt, run_after, last_heartbeat_at, priority_weight"#,
+        now,
+        lease_owner,
+        lease_expires_at,
+        now,
+        now,
+    )
+    .fetch_optional(db_pool)
+    .await?;
+    Ok(task_instance)
+}
+
+/// Record a heartbeat for a running TaskInstance, extending the lease that an
+/// executor holds on it.
+///
+/// Returns whether the executor still holds the running TaskInstance.
+pub async fn renew_task_instance_lease(
+    task_instance_id: &str,
+    lease_owner: &str,
+    now: &str,
+    lease_expires_at: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        "UPDATE task_instances SET last_heartbeat_at = ?, lease_expires_at = ? WHERE id = ? AND lease_owner = ? AND status = 'running'",
+        now,
+        lease_expires_at,
+        task_instance_id,
+        lease_owner,
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Record a heartbeat for the running TaskInstances with the given IDs that are
+/// held by `lease_owner`, extending their leases.
+///
+/// Returns the IDs of the TaskInstances whose leases were extended.
+pub async fn renew_task_instance_leases(
+    task_instance_ids: &[String],
+    lease_owner: &str,
+    now: &str,
+    lease_expires_at: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<Vec<String>, sqlx::Error> {
+    // SQLite can't bind a list, so the IDs are passed as a JSON array
+    let task_instance_ids = Json(task_instance_ids);
+    let rows = sqlx::query!(
+        "UPDATE task_instances SET last_heartbeat_at = ?, lease_expires_at = ? WHERE lease_owner = ? AND status = 'running' AND id IN (SELECT value FROM json_each(?)) RETURNING id",
+        now,
+        lease_expires_at,
+        lease_owner,
+        task_instance_ids,
+    )
+    .fetch_all(db_pool)
+    .await?;
+    Ok(rows.into_iter().map(|row| row.id).collect())
+}
+
+/// Get who holds the lease on a TaskInstance, if it is running
+pub async fn select_task_instance_lease_owner(
+    task_instance_id: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<Option<String>, sqlx::Error> {
+    let row = sqlx::query!(
+        "SELECT lease_owner FROM task_instances WHERE id = ? AND status = 'running'",
+        task_instance_id,
+    )
+    .fetch_optional(db_pool)
+    .await?;
+    Ok(row.and_then(|row| row.lease_owner))
+}
+
+/// Mark running TaskInstances as zombies when whoever runs them stopped sending
+/// heartbeats, so their lease expired, or never held a lease at all.
+///
+/// Returns the IDs of the zombie TaskInstances.
+pub async fn mark_zombie_task_instances(
+    now: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<Vec<String>, sqlx::Error> {
+    let rows = sqlx::query!(
+        "UPDATE task_instances SET status = 'zombie', execution_end = ? WHERE status = 'running' AND (lease_expires_at IS NULL OR lease_expires_at < ?) RETURNING id",
+        now,
+        now,
+    )
+    .fetch_all(db_pool)
+    .await?;
+    Ok(rows.into_iter().map(|row| row.id).collect())
+}
+
+/// Look up everything needed to run a claimed TaskInstance.
+///
+/// Tasks without their own timeout inherit the Pipeline's default.
+pub async fn select_task_assignment(
+    task_instance: TaskInstance,
+    db_pool: &Pool<Sqlite>,
+) -> Result<TaskAssignment, sqlx::Error> {
+    let pipeline = select_pipeline_by_id(&task_instance.pipeline_id, db_pool).await?;
+    let task = select_task_by_pipeline_id(&task_instance.pipeline_id, db_pool)
+        .await?
+        .into_iter()
+        .find(|task| task.id == task_instance.task_id)
+        .ok_or(sqlx::Error::RowNotFound)?;
+    let default_timeout = pipeline
+        .default_timeout
+        .and_then(|default_timeout| u32::try_from(default_timeout).ok());
+    let params = match task_instance.pipeline_run_id.as_deref() {
+        Some(pipeline_run_id) => {
+            select_pipeline_run_by_id(pipeline_run_id, db_pool)
+                .await?
+                .params
+                .0
+        }
+        None => HashMap::new(),
+    };
+    Ok(TaskAssignment {
+        task_instance,
+        command: task.command,
+        timeout: task.timeout.or(default_timeout),
+        params,
+    })
+}
+
+/// Move a pending TaskInstance on once the Tasks it depends on are done, either
+/// to the queue or to a status recording why it won't run.
+///
+/// Returns whether the TaskInstance was still pending and could move to the
+/// given status.
+pub async fn update_pending_task_instance(
+    task_instance_id: &str,
+    status: TaskStatus,
+    db_pool: &Pool<Sqlite>,
+) -> Result<bool, sqlx::Error> {
+    let from_status = TaskStatus::Pending;
+    if !from_status.can_transition_to(status) {
+        return Ok(false);
+    }
+    let result = sqlx::query!(
+        "UPDATE task_instances SET status = ? WHERE id = ? AND status = ?",
+        status,
+        task_instance_id,
+        from_status,
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Add new pending attempts of Tasks to a finished PipelineRun, and queue the
+/// run again so the scheduler picks them up.
+///
+/// Returns whether the run was finished and could be queued again.
+pub async fn clear_task_instances(
+    pipeline_run_id: &str,
+    task_instances: &[TaskInstance],
+    db_pool: &Pool<Sqlite>,
+) -> Result<bool, sqlx::Error> {
+    let (queued, running) = (PipelineRunStatus::Queued, PipelineRunStatus::Running);
+    let mut transaction = db_pool.begin().await?;
+    let result = sqlx::query!(
+        "UPDATE pipeline_runs SET status = ?, execution_end = NULL WHERE id = ? AND status NOT IN (?, ?)",
+        queued,
+        pipeline_run_id,
+        queued,
+        running,
+    )
+    .execute(&mut *transaction)
+    .await?;
+    if result.rows_affected() != 1 {
+        return Ok(false);
+    }
+    for task_instance in task_instances {
+        sqlx::query!(
+            "INSERT INTO task_instances (id, task_id, pipeline_id, pipeline_run_id, scheduled_time, execution_start, execution_end, status, exit_code, created_at, attempt, last_attempt, run_after, priority_weight) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            task_instance.id,
+            task_instance.task_id,
+            task_instance.pipeline_id,
+            task_instance.pipeline_run_id,
+            task_instance.scheduled_time,
+            task_instance.execution_start,
+            task_instance.execution_end,
+            task_instance.status,
+            task_instance.exit_code,
+            task_instance.created_at,
+            task_instance.attempt,
+            task_instance.last_attempt,
+            task_instance.run_after,
+            task_instance.priority_weight,
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+    transaction.commit().await?;
+    Ok(true)
+}
+
+/// Cancel a pending, queued or running TaskInstance that is in `from_status`.
+///
+/// The executor running the TaskInstance notices, stops its process and
+/// records when it ended. A TaskInstance that never started ends right away.
+/// Returns whether the TaskInstance was still in `from_status` and could be
+/// cancelled.
+pub async fn cancel_task_instance(
+    task_instance_id: &str,
+    from_status: TaskStatus,
+    execution_end: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<bool, sqlx::Error> {
+    let status = TaskStatus::Cancelled;
+    if !from_status.can_transition_to(status) {
+        return Ok(false);
+    }
+    let execution_end = (from_status != TaskStatus::Running).then_some(execution_end);
+    let result = sqlx::query!(
+        "UPDATE task_instances SET status = ?, execution_end = COALESCE(?, execution_end) WHERE id = ? AND status = ?",
+        status,
+        execution_end,
+        task_instance_id,
+        from_status,
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Get the IDs of cancelled TaskInstances whose process hasn't stopped yet
+pub async fn select_unfinished_cancelled_task_instance_ids(
+    db_pool: &Pool<Sqlite>,
+) -> Result<Vec<String>, sqlx::Error> {
+    let rows = sqlx::query!(
+        "SELECT id FROM task_instances WHERE status = 'cancelled' AND execution_end = ''"
+    )
+    .fetch_all(db_pool)
+    .await?;
+    Ok(rows.into_iter().map(|row| row.id).collect())
+}
+
+/// Record how the process of a cancelled TaskInstance leased to `lease_owner` ended
+pub async fn finish_cancelled_task_instance(
+    task_instance_id: &str,
+    lease_owner: &str,
+    exit_code: Option<i64>,
+    execution_end: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE task_instances SET exit_code = ?, execution_end = ? WHERE id = ? AND status = 'cancelled' AND lease_owner = ?",
+        exit_code,
+        execution_end,
+        task_instance_id,
+        lease_owner,
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(())
+}
+
+/// Get a TaskInstance by ID
+pub async fn select_task_instance_by_id(
+    task_instance_id: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<TaskInstance, sqlx::Error> {
+    let task_instance = sqlx::query_as!(
+        TaskInstance,
+        r#"SELECT id, task_id, pipeline_id, pipeline_run_id, scheduled_time, execution_start, execution_end, status AS "status: TaskStatus", exit_code, created_at, attempt, last_attempt, run_after, last_heartbeat_at, priority_weight FROM task_instances WHERE id = ?"#,
+        task_instance_id
+    )
+    .fetch_one(db_pool)
+    .await?;
+    Ok(task_instance)
+}
+
+/// Get the TaskInstances of a PipelineRun
+pub async fn select_task_instances_by_pipeline_run_id(
+    pipeline_run_id: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<Vec<TaskInstance>, sqlx::Error> {
+    let task_instances = sqlx::query_as!(
+        TaskInstance,
+        r#"SELECT id, task_id, pipeline_id, pipeline_run_id, scheduled_time, execution_start, execution_end, status AS "status: TaskStatus", exit_code, created_at, attempt, last_attempt, run_after, last_heartbeat_at, priority_weight FROM task_instances WHERE pipeline_run_id = ? ORDER BY created_at"#,
+        pipeline_run_id
+    )
+    .fetch_all(db_pool)
+    .await?;
+    Ok(task_instances)
+}
+
+/// Get all TaskInstances, optionally only those with a given status
+pub async fn select_task_instances(
+    status: Option<TaskStatus>,
+    db_pool: &Pool<Sqlite>,
+) -> Result<Vec<TaskInstance>, sqlx::Error> {
+    let task_instances = sqlx::query_as!(
+        TaskInstance,
+        r#"SELECT id, task_id, pipeline_id, pipeline_run_id, scheduled_time, execution_start, execution_end, status AS "status: TaskStatus", exit_code, created_at, attempt, last_attempt, run_after, last_heartbeat_at, priority_weight FROM task_instances WHERE ? IS NULL OR status = ?"#,
+        status,
+        status
+    )
+    .fetch_all(db_pool)
+    .await?;
+    Ok(task_instances)
+}
+
+/// Get the log lines of a TaskInstance that come after a given log ID
+pub async fn select_task_logs_after_id(
+    task_instance_id: &str,
+    after_id: i64,
+    db_pool: &Pool<Sqlite>,
+) -> Result<Vec<TaskLog>, sqlx::Error> {
+    let task_logs = sqlx::query_as!(
+        TaskLog,
+        "SELECT * FROM task_logs WHERE task_instance_id = ? AND id > ? ORDER BY id",
+        task_instance_id,
+        after_id
+    )
+    .fetch_all(db_pool)
+    .await?;
+    Ok(task_logs)
+}
+
+/// Insert the log lines of a TaskInstance
+pub async fn insert_task_logs(
+    task_logs: &[TaskLog],
+    db_pool: &Pool<Sqlite>,
+) -> Result<(), sqlx::Error> {
+    let mut transaction = db_pool.begin().await?;
+    for task_log in task_logs {
+        sqlx::query!(
+            "INSERT INTO task_logs (task_instance_id, timestamp, stream, line) VALUES(?, ?, ?, ?)",
+            task_log.task_instance_id,
+            task_log.timestamp,
+            task_log.stream,
+            task_log.line,
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+    transaction.commit().await?;
     Ok(())
 }
 
+/// Get the log lines of a TaskInstance, in the order they were produced
+pub async fn select_task_logs_by_task_instance_id(
+    task_instance_id: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<Vec<TaskLog>, sqlx::Error> {
+    let task_logs = sqlx::query_as!(
+        TaskLog,
+        "SELECT * FROM task_logs WHERE task_instance_id = ? ORDER BY id",
+        task_instance_id
+    )
+    .fetch_all(db_pool)
+    .await?;
+    Ok(task_logs)
+}
+
 /// Upsert a Pipeline
+///
+/// Changing the schedule resets `next_run_at` so the scheduler recomputes it.
+/// `is_paused` only applies when the Pipeline is created, so re-registering
+/// a Pipeline doesn't undo a pause or resume.
 pub async fn upsert_pipeline(
     pipeline: &Pipeline,
     db_pool: &Pool<Sqlite>,
 ) -> Result<(), sqlx::Error> {
     sqlx::query!(
-        "INSERT INTO pipelines (id, schedule) VALUES(?, ?) ON CONFLICT(id) DO UPDATE SET schedule = ?",
+        "INSERT INTO pipelines (id, schedule, timezone, start_date, end_date, catchup, default_timeout, is_paused, max_active_runs, overlap_policy, calendar, holiday_policy) VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) ON CONFLICT(id) DO UPDATE SET next_run_at = CASE WHEN schedule = excluded.schedule AND timezone IS excluded.timezone AND start_date IS excluded.start_date AND end_date IS excluded.end_date AND calendar IS excluded.calendar AND holiday_policy = excluded.holiday_policy THEN next_run_at ELSE NULL END, schedule = excluded.schedule, timezone = excluded.timezone, start_date = excluded.start_date, end_date = excluded.end_date, catchup = excluded.catchup, default_timeout = excluded.default_timeout, max_active_runs = excluded.max_active_runs, overlap_policy = excluded.overlap_policy, calendar = excluded.calendar, holiday_policy = excluded.holiday_policy",
         pipeline.id,
         pipeline.schedule,
-        pipeline.schedule,
+        pipeline.timezone,
+        pipeline.start_date,
+        pipeline.end_date,
+        pipeline.catchup,
+        pipeline.default_timeout,
+        pipeline.is_paused,
+        pipeline.max_active_runs,
+        pipeline.overlap_policy,
+        pipeline.calendar,
+        pipeline.holiday_policy,
     )
     .execute(db_pool)
     .await?;
     Ok(())
 }
 
+/// Pause or resume a Pipeline.
+///
+/// Returns whether the Pipeline exists.
+pub async fn set_pipeline_paused(
+    pipeline_id: &str,
+    is_paused: bool,
+    db_pool: &Pool<Sqlite>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        "UPDATE pipelines SET is_paused = ? WHERE id = ?",
+        is_paused,
+        pipeline_id,
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
 /// Insert a Pipeline
 pub async fn insert_pipeline(
     pipeline: Pipeline,
     db_pool: &Pool<Sqlite>,
 ) -> Result<(), sqlx::Error> {
     sqlx::query!(
-        "INSERT INTO pipelines (id, schedule) VALUES(?, ?)",
+        "INSERT INTO pipelines (id, schedule, timezone, start_date, end_date, catchup, default_timeout, is_paused, max_active_runs, overlap_policy, calendar, holiday_policy) VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         pipeline.id,
         pipeline.schedule,
+        pipeline.timezone,
+        pipeline.start_date,
+        pipeline.end_date,
+        pipeline.catchup,
+        pipeline.default_timeout,
+        pipeline.is_paused,
+        pipeline.max_active_runs,
+        pipeline.overlap_policy,
+        pipeline.calendar,
+        pipeline.holiday_policy,
     )
     .execute(db_pool)
     .await?;
     Ok(())
 }
 
+/// A row from the `tasks` table, before its dependencies are attached
+struct TaskRow {
+    id: String,
+    pipeline_id: String,
+    command: String,
+    retries: i64,
+    retry_delay: i64,
+    retry_backoff: String,
+    timeout: Option<i64>,
+    pool: Option<String>,
+    pool_slots: i64,
+    priority_weight: i64,
+    weight_rule: String,
+}
+impl TaskRow {
+    fn into_task(self, dependencies: &[TaskDependency]) -> Task {
+        let depends_on = dependencies
+            .iter()
+            .filter(|dependency| dependency.task_id == self.id)
+            .map(|dependency| dependency.depends_on.clone())
+            .collect();
+        Task {
+            id: self.id,
+            pipeline_id: self.pipeline_id,
+            command: self.command,
+            depends_on,
+            retries: u32::try_from(self.retries).unwrap_or_default(),
+            retry_delay: u32::try_from(self.retry_delay).unwrap_or_default(),
+            retry_backoff: self.retry_backoff.parse().unwrap_or_default(),
+            timeout: self.timeout.and_then(|timeout| u32::try_from(timeout).ok()),
+            pool: self.pool,
+            pool_slots: u32::try_from(self.pool_slots).unwrap_or_default(),
+            priority_weight: u32::try_from(self.priority_weight).unwrap_or_default(),
+            weight_rule: self.weight_rule.parse().unwrap_or_default(),
+        }
+    }
+}
+
+/// A row from the `task_dependencies` table
+struct TaskDependency {
+    task_id: String,
+    depends_on: String,
+}
+
+/// Attach the upstream dependencies to each Task row
+async fn attach_dependencies(
+    rows: Vec<TaskRow>,
+    db_pool: &Pool<Sqlite>,
+) -> Result<Vec<Task>, sqlx::Error> {
+    let dependencies = sqlx::query_as!(
+        TaskDependency,
+        "SELECT task_id, depends_on FROM task_dependencies ORDER BY task_id, depends_on"
+    )
+    .fetch_all(db_pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| row.into_task(&dependencies))
+        .collect())
+}
+
+/// Replace the dependencies of a Task within an open transaction
+async fn replace_task_dependencies(
+    task: &Task,
+    transaction: &mut Transaction<'_, Sqlite>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!("DELETE FROM task_dependencies WHERE task_id = ?", task.id)
+        .execute(&mut **transaction)
+        .await?;
+    for upstream in &task.depends_on {
+        sqlx::query!(
+            "INSERT INTO task_dependencies (task_id, depends_on) VALUES(?, ?)",
+            task.id,
+            upstream,
+        )
+        .execute(&mut **transaction)
+        .await?;
+    }
+    Ok(())
+}
+
 /// Insert a Task
 pub async fn insert_task(task: Task, db_pool: &Pool<Sqlite>) -> Result<(), sqlx::Error> {
+    let mut transaction = db_pool.begin().await?;
+    let retry_backoff = task.retry_backoff.as_str();
+    let weight_rule = task.weight_rule.as_str();
     sqlx::query!(
-        "INSERT INTO tasks (id, pipeline_id, command) VALUES(?, ?, ?)",
+        "INSERT INTO tasks (id, pipeline_id, command, retries, retry_delay, retry_backoff, timeout, pool, pool_slots, priority_weight, weight_rule) VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         task.id,
         task.pipeline_id,
         task.command,
+        task.retries,
+        task.retry_delay,
+        retry_backoff,
+        task.timeout,
+        task.pool,
+        task.pool_slots,
+        task.priority_weight,
+        weight_rule,
     )
-    .execute(db_pool)
+    .execute(&mut *transaction)
     .await?;
+    replace_task_dependencies(&task, &mut transaction).await?;
+    transaction.commit().await?;
     Ok(())
 }
 
 /// Upsert a Task
 pub async fn upsert_task(task: &Task, db_pool: &Pool<Sqlite>) -> Result<(), sqlx::Error> {
+    let mut transaction = db_pool.begin().await?;
+    let retry_backoff = task.retry_backoff.as_str();
+    let weight_rule = task.weight_rule.as_str();
     sqlx::query!(
-        "INSERT INTO tasks (id, pipeline_id, command) VALUES(?, ?, ?) ON CONFLICT(id) DO UPDATE SET pipeline_id = ?, command = ?",
+        "INSERT INTO tasks (id, pipeline_id, command, retries, retry_delay, retry_backoff, timeout, pool, pool_slots, priority_weight, weight_rule) VALUES(?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) ON CONFLICT(id) DO UPDATE SET pipeline_id = excluded.pipeline_id, command = excluded.command, retries = excluded.retries, retry_delay = excluded.retry_delay, retry_backoff = excluded.retry_backoff, timeout = excluded.timeout, pool = excluded.pool, pool_slots = excluded.pool_slots, priority_weight = excluded.priority_weight, weight_rule = excluded.weight_rule",
         task.id,
         task.pipeline_id,
         task.command,
-        task.pipeline_id,
-        task.command,
+        task.retries,
+        task.retry_delay,
+        retry_backoff,
+        task.timeout,
+        task.pool,
+        task.pool_slots,
+        task.priority_weight,
+        weight_rule,
     )
-    .execute(db_pool)
+    .execute(&mut *transaction)
     .await?;
+    replace_task_dependencies(task, &mut transaction).await?;
+    transaction.commit().await?;
     Ok(())
 }
 
@@ -87,29 +664,41 @@ pub async fn select_task_by_pipeline_id(
     pipeline_id: &str,
     db_pool: &Pool<Sqlite>,
 ) -> Result<Vec<Task>, sqlx::Error> {
-    let tasks: Vec<Task> = sqlx::query_as!(
-        Task,
+    let rows = sqlx::query_as!(
+        TaskRow,
         "SELECT * FROM tasks WHERE pipeline_id = ?",
         pipeline_id
     )
     .fetch_all(db_pool)
     .await?;
-    Ok(tasks)
+    attach_dependencies(rows, db_pool).await
+}
+
+/// Get a Task by ID
+pub async fn select_task_by_id(task_id: &str, db_pool: &Pool<Sqlite>) -> Result<Task, sqlx::Error> {
+    let row = sqlx::query_as!(TaskRow, "SELECT * FROM tasks WHERE id = ?", task_id)
+        .fetch_one(db_pool)
+        .await?;
+    let mut tasks = attach_dependencies(vec![row], db_pool).await?;
+    Ok(tasks.remove(0))
 }
 
 /// Get all Tasks
 pub async fn select_tasks(db_pool: &Pool<Sqlite>) -> Result<Vec<Task>, sqlx::Error> {
-    let tasks = sqlx::query_as!(Task, "SELECT * FROM tasks ORDER BY pipeline_id")
+    let rows = sqlx::query_as!(TaskRow, "SELECT * FROM tasks ORDER BY pipeline_id")
         .fetch_all(db_pool)
         .await?;
-    Ok(tasks)
+    attach_dependencies(rows, db_pool).await
 }
 
 /// Get all Pipelines
 pub async fn select_pipelines(db_pool: &Pool<Sqlite>) -> Result<Vec<Pipeline>, sqlx::Error> {
-    let pipelines = sqlx::query_as!(Pipeline, "SELECT * FROM pipelines",)
-        .fetch_all(db_pool)
-        .await?;
+    let pipelines = sqlx::query_as!(
+        Pipeline,
+        r#"SELECT id, schedule, timezone, start_date, end_date, next_run_at, last_run_at, catchup, default_timeout, is_paused, max_active_runs, overlap_policy AS "overlap_policy: OverlapPolicy", calendar, holiday_policy AS "holiday_policy: HolidayPolicy" FROM pipelines"#,
+    )
+    .fetch_all(db_pool)
+    .await?;
     Ok(pipelines)
 }
 
@@ -120,10 +709,536 @@ pub async fn select_pipeline_by_id(
 ) -> Result<Pipeline, sqlx::Error> {
     let pipeline = sqlx::query_as!(
         Pipeline,
-        "SELECT * FROM pipelines WHERE id = ?",
+        r#"SELECT id, schedule, timezone, start_date, end_date, next_run_at, last_run_at, catchup, default_timeout, is_paused, max_active_runs, overlap_policy AS "overlap_policy: OverlapPolicy", calendar, holiday_policy AS "holiday_policy: HolidayPolicy" FROM pipelines WHERE id = ?"#,
         pipeline_id
     )
     .fetch_one(db_pool)
     .await?;
     Ok(pipeline)
 }
+
+/// Set the next run time of a Pipeline that hasn't been scheduled yet
+pub async fn init_pipeline_next_run_at(
+    pipeline_id: &str,
+    next_run_at: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "UPDATE pipelines SET next_run_at = ? WHERE id = ? AND next_run_at IS NULL",
+        next_run_at,
+        pipeline_id,
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(())
+}
+
+/// Advance a Pipeline's schedule after it becomes due, and queue the
+/// PipelineRuns for the ticks that came due.
+///
+/// The update is a compare-and-set that only applies if `next_run_at` still
+/// equals `scheduled_time`, so the ticks are claimed exactly once. It happens in
+/// the same transaction as inserting the runs, so a claimed tick always gets its
+/// run. `next_run_at` is cleared once the schedule has ended.
+/// Returns whether the claim succeeded.
+pub async fn advance_pipeline_schedule(
+    pipeline_id: &str,
+    scheduled_time: &str,
+    last_run_at: &str,
+    next_run_at: Option<&str>,
+    pipeline_runs: &[PipelineRun],
+    db_pool: &Pool<Sqlite>,
+) -> Result<bool, sqlx::Error> {
+    let mut transaction = db_pool.begin().await?;
+    let result = sqlx::query!(
+        "UPDATE pipelines SET last_run_at = ?, next_run_at = ? WHERE id = ? AND next_run_at = ?",
+        last_run_at,
+        next_run_at,
+        pipeline_id,
+        scheduled_time,
+    )
+    .execute(&mut *transaction)
+    .await?;
+    if result.rows_affected() != 1 {
+        return Ok(false);
+    }
+    for pipeline_run in pipeline_runs {
+        sqlx::query!(
+            "INSERT INTO pipeline_runs (id, pipeline_id, scheduled_time, trigger, status, created_at, params) VALUES(?, ?, ?, ?, ?, ?, ?) ON CONFLICT DO NOTHING",
+            pipeline_run.id,
+            pipeline_run.pipeline_id,
+            pipeline_run.scheduled_time,
+            pipeline_run.trigger,
+            pipeline_run.status,
+            pipeline_run.created_at,
+            pipeline_run.params,
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+    transaction.commit().await?;
+    Ok(true)
+}
+
+/// Insert a PipelineRun, ignoring runs that already exist for the same tick.
+/// Manual runs only clash with other manual runs, so they never take the
+/// place of a scheduled run.
+///
+/// Returns whether the run was inserted.
+pub async fn insert_pipeline_run(
+    pipeline_run: &PipelineRun,
+    db_pool: &Pool<Sqlite>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        "INSERT INTO pipeline_runs (id, pipeline_id, scheduled_time, trigger, status, created_at, params) VALUES(?, ?, ?, ?, ?, ?, ?) ON CONFLICT DO NOTHING",
+        pipeline_run.id,
+        pipeline_run.pipeline_id,
+        pipeline_run.scheduled_time,
+        pipeline_run.trigger,
+        pipeline_run.status,
+        pipeline_run.created_at,
+        pipeline_run.params,
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Get all PipelineRuns with a given status, oldest tick first
+pub async fn select_pipeline_runs_by_status(
+    status: PipelineRunStatus,
+    db_pool: &Pool<Sqlite>,
+) -> Result<Vec<PipelineRun>, sqlx::Error> {
+    let pipeline_runs = sqlx::query_as!(
+        PipelineRun,
+        r#"SELECT id, pipeline_id, scheduled_time, trigger AS "trigger: RunTrigger", status AS "status: PipelineRunStatus", created_at, execution_start, execution_end, params AS "params: Json<HashMap<String, String>>" FROM pipeline_runs WHERE status = ? ORDER BY scheduled_time"#,
+        status
+    )
+    .fetch_all(db_pool)
+    .await?;
+    Ok(pipeline_runs)
+}
+
+/// Get the PipelineRuns of a Pipeline, most recent tick first
+pub async fn select_pipeline_runs_by_pipeline_id(
+    pipeline_id: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<Vec<PipelineRun>, sqlx::Error> {
+    let pipeline_runs = sqlx::query_as!(
+        PipelineRun,
+        r#"SELECT id, pipeline_id, scheduled_time, trigger AS "trigger: RunTrigger", status AS "status: PipelineRunStatus", created_at, execution_start, execution_end, params AS "params: Json<HashMap<String, String>>" FROM pipeline_runs WHERE pipeline_id = ? ORDER BY scheduled_time DESC"#,
+        pipeline_id
+    )
+    .fetch_all(db_pool)
+    .await?;
+    Ok(pipeline_runs)
+}
+
+/// Get a PipelineRun by ID
+pub async fn select_pipeline_run_by_id(
+    pipeline_run_id: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<PipelineRun, sqlx::Error> {
+    let pipeline_run = sqlx::query_as!(
+        PipelineRun,
+        r#"SELECT id, pipeline_id, scheduled_time, trigger AS "trigger: RunTrigger", status AS "status: PipelineRunStatus", created_at, execution_start, execution_end, params AS "params: Json<HashMap<String, String>>" FROM pipeline_runs WHERE id = ?"#,
+        pipeline_run_id
+    )
+    .fetch_one(db_pool)
+    .await?;
+    Ok(pipeline_run)
+}
+
+/// Move a queued PipelineRun to running.
+///
+/// Returns whether the run was still queued.
+pub async fn start_pipeline_run(
+    pipeline_run_id: &str,
+    execution_start: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<bool, sqlx::Error> {
+    let (from_status, status) = (PipelineRunStatus::Queued, PipelineRunStatus::Running);
+    let result = sqlx::query!(
+        "UPDATE pipeline_runs SET status = ?, execution_start = ? WHERE id = ? AND status = ?",
+        status,
+        execution_start,
+        pipeline_run_id,
+        from_status,
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Drop a queued PipelineRun without running it.
+///
+/// Returns whether the run was still queued.
+pub async fn skip_pipeline_run(
+    pipeline_run_id: &str,
+    execution_end: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<bool, sqlx::Error> {
+    let (from_status, status) = (PipelineRunStatus::Queued, PipelineRunStatus::Skipped);
+    let result = sqlx::query!(
+        "UPDATE pipeline_runs SET status = ?, execution_end = ? WHERE id = ? AND status = ?",
+        status,
+        execution_end,
+        pipeline_run_id,
+        from_status,
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Cancel a queued or running PipelineRun along with its unfinished TaskInstances,
+/// recording the Tasks that never started as cancelled too.
+///
+/// Returns whether the run could be cancelled.
+pub async fn cancel_pipeline_run(
+    pipeline_run_id: &str,
+    execution_end: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<bool, sqlx::Error> {
+    let status = PipelineRunStatus::Cancelled;
+    let (queued, running) = (PipelineRunStatus::Queued, PipelineRunStatus::Running);
+    let mut transaction = db_pool.begin().await?;
+    let result = sqlx::query!(
+        "UPDATE pipeline_runs SET status = ?, execution_end = ? WHERE id = ? AND status IN (?, ?)",
+        status,
+        execution_end,
+        pipeline_run_id,
+        queued,
+        running,
+    )
+    .execute(&mut *transaction)
+    .await?;
+    if result.rows_affected() != 1 {
+        return Ok(false);
+    }
+    // Running TaskInstances end once their executor has stopped them
+    sqlx::query!(
+        "UPDATE task_instances SET status = ?, execution_end = CASE WHEN status = ? THEN execution_end ELSE ? END WHERE pipeline_run_id = ? AND status IN (?, ?, ?)",
+        TaskStatus::Cancelled,
+        TaskStatus::Running,
+        execution_end,
+        pipeline_run_id,
+        TaskStatus::Pending,
+        TaskStatus::Queued,
+        TaskStatus::Running,
+    )
+    .execute(&mut *transaction)
+    .await?;
+    // Tasks that never got a TaskInstance won't run either
+    let unstarted_tasks = sqlx::query!(
+        "SELECT tasks.id AS task_id, pipeline_runs.pipeline_id, pipeline_runs.scheduled_time FROM tasks JOIN pipeline_runs ON pipeline_runs.pipeline_id = tasks.pipeline_id WHERE pipeline_runs.id = ? AND NOT EXISTS (SELECT 1 FROM task_instances WHERE task_instances.pipeline_run_id = pipeline_runs.id AND task_instances.task_id = tasks.id)",
+        pipeline_run_id,
+    )
+    .fetch_all(&mut *transaction)
+    .await?;
+    for task in unstarted_tasks {
+        let id = Uuid::new_v4().to_string();
+        sqlx::query!(
+            "INSERT INTO task_instances (id, task_id, pipeline_id, pipeline_run_id, scheduled_time, execution_start, execution_end, status, created_at) VALUES (?, ?, ?, ?, ?, '', ?, ?, ?)",
+            id,
+            task.task_id,
+            task.pipeline_id,
+            pipeline_run_id,
+            task.scheduled_time,
+            execution_end,
+            TaskStatus::Cancelled,
+            execution_end,
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+    transaction.commit().await?;
+    Ok(true)
+}
+
+/// Record the final status of a running PipelineRun.
+///
+/// Returns whether the run was still running and could move to the given status.
+pub async fn finish_pipeline_run(
+    pipeline_run_id: &str,
+    status: PipelineRunStatus,
+    execution_end: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<bool, sqlx::Error> {
+    let from_status = PipelineRunStatus::Running;
+    if !from_status.can_transition_to(status) {
+        return Ok(false);
+    }
+    let result = sqlx::query!(
+        "UPDATE pipeline_runs SET status = ?, execution_end = ? WHERE id = ? AND status = ?",
+        status,
+        execution_end,
+        pipeline_run_id,
+        from_status,
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Insert a newly registered Worker
+pub async fn insert_worker(worker: &Worker, db_pool: &Pool<Sqlite>) -> Result<(), sqlx::Error> {
+    sqlx::query!(
+        "INSERT INTO workers (id, hostname, registered_at, last_heartbeat_at) VALUES(?, ?, ?, ?)",
+        worker.id,
+        worker.hostname,
+        worker.registered_at,
+        worker.last_heartbeat_at,
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(())
+}
+
+/// Get all Workers
+pub async fn select_workers(db_pool: &Pool<Sqlite>) -> Result<Vec<Worker>, sqlx::Error> {
+    let workers = sqlx::query_as!(Worker, "SELECT * FROM workers ORDER BY registered_at")
+        .fetch_all(db_pool)
+        .await?;
+    Ok(workers)
+}
+
+/// Get a Worker by ID
+pub async fn select_worker_by_id(
+    worker_id: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<Worker, sqlx::Error> {
+    let worker = sqlx::query_as!(Worker, "SELECT * FROM workers WHERE id = ?", worker_id)
+        .fetch_one(db_pool)
+        .await?;
+    Ok(worker)
+}
+
+/// Record that a Worker is still alive.
+///
+/// Returns whether the Worker is registered.
+pub async fn update_worker_heartbeat(
+    worker_id: &str,
+    last_heartbeat_at: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        "UPDATE workers SET last_heartbeat_at = ? WHERE id = ?",
+        last_heartbeat_at,
+        worker_id,
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Take a leader lease if it's free or expired, or renew it if it's already
+/// held by the given holder.
+///
+/// Returns whether the holder is now the leader.
+pub async fn acquire_leader_lease(
+    name: &str,
+    holder: &str,
+    now: &str,
+    expires_at: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query!(
+        "INSERT INTO leader_leases (name, holder, acquired_at, last_heartbeat_at, expires_at) VALUES (?, ?, ?, ?, ?)
+        ON CONFLICT (name) DO UPDATE SET
+            acquired_at = CASE WHEN holder = excluded.holder THEN acquired_at ELSE excluded.acquired_at END
+            , holder = excluded.holder
+            , last_heartbeat_at = excluded.last_heartbeat_at
+            , expires_at = excluded.expires_at
+        WHERE holder = excluded.holder OR expires_at < excluded.last_heartbeat_at",
+        name,
+        holder,
+        now,
+        now,
+        expires_at,
+    )
+    .execute(db_pool)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Get the leader lease with the given name, unless it has expired
+pub async fn select_leader_lease(
+    name: &str,
+    now: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<Option<LeaderLease>, sqlx::Error> {
+    let leader_lease = sqlx::query_as!(
+        LeaderLease,
+        "SELECT * FROM leader_leases WHERE name = ? AND expires_at >= ?",
+        name,
+        now,
+    )
+    .fetch_optional(db_pool)
+    .await?;
+    Ok(leader_lease)
+}
+
+/// Create a pool, or change the number of slots of an existing one.
+///
+/// Returns whether the pool was saved. It isn't if a Task in the pool needs
+/// more slots than that, or if its running Tasks already take up more.
+pub async fn upsert_pool(pool: &ResourcePool, db_pool: &Pool<Sqlite>) -> Result<bool, sqlx::Error> {
+    let mut transaction = db_pool.begin().await?;
+    let row = sqlx::query!(
+        r#"SELECT (
+            SELECT COALESCE(MAX(pool_slots), 0) FROM tasks WHERE pool = ?
+        ) AS "largest_pool_slots!: i64", (
+            SELECT COALESCE(SUM(tasks.pool_slots), 0) FROM task_instances
+            JOIN tasks ON tasks.id = task_instances.task_id AND tasks.pipeline_id = task_instances.pipeline_id
+            WHERE task_instances.status = ? AND tasks.pool = ?
+        ) AS "used_slots!: i64""#,
+        pool.id,
+        TaskStatus::Running,
+        pool.id,
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+    if row.largest_pool_slots > pool.slots || row.used_slots > pool.slots {
+        return Ok(false);
+    }
+    sqlx::query!(
+        "INSERT INTO pools (id, slots) VALUES(?, ?) ON CONFLICT(id) DO UPDATE SET slots = excluded.slots",
+        pool.id,
+        pool.slots,
+    )
+    .execute(&mut *transaction)
+    .await?;
+    transaction.commit().await?;
+    Ok(true)
+}
+
+/// Fail queued TaskInstances whose Task is in a pool that doesn't exist, or
+/// that needs more slots than its pool has, as they can never be claimed.
+///
+/// Returns the IDs of the failed TaskInstances.
+pub async fn fail_unclaimable_task_instances(
+    execution_end: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<Vec<String>, sqlx::Error> {
+    let (from_status, status) = (TaskStatus::Queued, TaskStatus::Failed);
+    let rows = sqlx::query!(
+        "UPDATE task_instances SET status = ?, execution_end = ? WHERE status = ? AND id IN (
+            SELECT task_instances.id FROM task_instances
+            JOIN tasks ON tasks.id = task_instances.task_id AND tasks.pipeline_id = task_instances.pipeline_id
+            LEFT JOIN pools ON pools.id = tasks.pool
+            WHERE task_instances.status = ? AND tasks.pool IS NOT NULL AND (pools.id IS NULL OR tasks.pool_slots > pools.slots)
+        ) RETURNING id",
+        status,
+        execution_end,
+        from_status,
+        from_status,
+    )
+    .fetch_all(db_pool)
+    .await?;
+    Ok(rows.into_iter().map(|row| row.id).collect())
+}
+
+/// Get all pools along with the slots taken up by running Tasks
+pub async fn select_pools(db_pool: &Pool<Sqlite>) -> Result<Vec<ResourcePool>, sqlx::Error> {
+    let pools = sqlx::query_as!(
+        ResourcePool,
+        r#"SELECT pools.id, pools.slots, (
+            SELECT COALESCE(SUM(tasks.pool_slots), 0) FROM task_instances
+            JOIN tasks ON tasks.id = task_instances.task_id AND tasks.pipeline_id = task_instances.pipeline_id
+            WHERE task_instances.status = 'running' AND tasks.pool = pools.id
+        ) AS "used_slots!: i64" FROM pools ORDER BY pools.id"#
+    )
+    .fetch_all(db_pool)
+    .await?;
+    Ok(pools)
+}
+
+/// Get a pool by ID, along with the slots taken up by running Tasks
+pub async fn select_pool_by_id(
+    pool_id: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<ResourcePool, sqlx::Error> {
+    let pool = sqlx::query_as!(
+        ResourcePool,
+        r#"SELECT pools.id, pools.slots, (
+            SELECT COALESCE(SUM(tasks.pool_slots), 0) FROM task_instances
+            JOIN tasks ON tasks.id = task_instances.task_id AND tasks.pipeline_id = task_instances.pipeline_id
+            WHERE task_instances.status = 'running' AND tasks.pool = pools.id
+        ) AS "used_slots!: i64" FROM pools WHERE pools.id = ?"#,
+        pool_id
+    )
+    .fetch_one(db_pool)
+    .await?;
+    Ok(pool)
+}
+
+/// Create a calendar, or replace the holidays of an existing one.
+///
+/// Pipelines on a calendar whose holidays changed have their next run worked
+/// out again.
+pub async fn upsert_calendar(
+    calendar: &Calendar,
+    db_pool: &Pool<Sqlite>,
+) -> Result<(), sqlx::Error> {
+    let mut transaction = db_pool.begin().await?;
+    let result = sqlx::query!(
+        "INSERT INTO calendars (id, holidays) VALUES(?, ?) ON CONFLICT(id) DO UPDATE SET holidays = excluded.holidays WHERE holidays != excluded.holidays",
+        calendar.id,
+        calendar.holidays,
+    )
+    .execute(&mut *transaction)
+    .await?;
+    if result.rows_affected() == 1 {
+        sqlx::query!(
+            "UPDATE pipelines SET next_run_at = NULL WHERE calendar = ?",
+            calendar.id,
+        )
+        .execute(&mut *transaction)
+        .await?;
+    }
+    transaction.commit().await?;
+    Ok(())
+}
+
+/// Get all calendars
+pub async fn select_calendars(db_pool: &Pool<Sqlite>) -> Result<Vec<Calendar>, sqlx::Error> {
+    let calendars = sqlx::query_as!(
+        Calendar,
+        r#"SELECT id, holidays AS "holidays: Json<Vec<String>>" FROM calendars ORDER BY id"#
+    )
+    .fetch_all(db_pool)
+    .await?;
+    Ok(calendars)
+}
+
+/// Get a calendar by ID
+pub async fn select_calendar_by_id(
+    calendar_id: &str,
+    db_pool: &Pool<Sqlite>,
+) -> Result<Calendar, sqlx::Error> {
+    let calendar = sqlx::query_as!(
+        Calendar,
+        r#"SELECT id, holidays AS "holidays: Json<Vec<String>>" FROM calendars WHERE id = ?"#,
+        calendar_id
+    )
+    .fetch_one(db_pool)
+    .await?;
+    Ok(calendar)
+}
+
+/// Get the calendar that a Pipeline is on, unless it has none or the calendar
+/// doesn't exist
+pub async fn select_pipeline_calendar(
+    pipeline: &Pipeline,
+    db_pool: &Pool<Sqlite>,
+) -> Result<Option<Calendar>, sqlx::Error> {
+    let calendar = sqlx::query_as!(
+        Calendar,
+        r#"SELECT id, holidays AS "holidays: Json<Vec<String>>" FROM calendars WHERE id = ?"#,
+        pipeline.calendar
+    )
+    .fetch_optional(db_pool)
+    .await?;
+    Ok(calendar)
+}
@@ -0,0 +1,459 @@
+use super::calendar;
+use super::models::{Calendar, HolidayPolicy, Pipeline};
+use chrono::{
+    DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone,
+    Timelike, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use cron_parser::{parse, parse_field};
+use std::collections::BTreeSet;
+
+/// Upper bound on the number of fire times enumerated for a single range
+pub const MAX_FIRE_TIMES: usize = 10_000;
+
+/// Prefix of a schedule that fires at a fixed interval, e.g. `@every 15m`
+pub const EVERY_PREFIX: &str = "@every ";
+
+/// How often a schedule fires, before it is bounded by its start and end
+#[derive(Debug, Clone)]
+enum Recurrence {
+    /// A 5-field cron expression matched against the wall clock, and the
+    /// seconds of every matching minute that it fires at
+    Cron {
+        fields: String,
+        seconds: BTreeSet<u32>,
+    },
+    /// A fixed interval, counted from the start of the schedule
+    Interval(Duration),
+}
+
+/// A parsed Pipeline schedule, which computes the times that the Pipeline fires
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    recurrence: Recurrence,
+    timezone: Tz,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    /// Holidays that the schedule avoids, and how it avoids them
+    holidays: Option<(BTreeSet<NaiveDate>, HolidayPolicy)>,
+}
+impl Schedule {
+    /// Parse a schedule, which is one of:
+    /// * a 5-field cron expression, or a 6-field one that starts with seconds
+    /// * a preset: `@yearly`, `@monthly`, `@weekly`, `@daily` or `@hourly`
+    /// * a fixed interval such as `@every 15m`, made up of `d`, `h`, `m` and `s`
+    ///
+    /// The start and end dates are inclusive bounds on the fire times, given
+    /// as RFC 3339 timestamps or as `YYYY-MM-DD` dates in the time zone.
+    pub fn parse(
+        schedule: &str,
+        timezone: Option<&str>,
+        start_date: Option<&str>,
+        end_date: Option<&str>,
+    ) -> Result<Self, String> {
+        let timezone = parse_timezone(timezone)?;
+        let recurrence = parse_recurrence(schedule)?;
+        let start = start_date
+            .map(|start_date| parse_bound(start_date, timezone, false))
+            .transpose()?;
+        let end = end_date
+            .map(|end_date| parse_bound(end_date, timezone, true))
+            .transpose()?;
+        if let (Some(start), Some(end)) = (start, end) {
+            if start > end {
+                return Err(format!(
+                    "start_date '{}' is after end_date '{}'!",
+                    format_timestamp(&start),
+                    format_timestamp(&end)
+                ));
+            }
+        }
+        Ok(Self {
+            recurrence,
+            timezone,
+            start,
+            end,
+            holidays: None,
+        })
+    }
+
+    /// Parse the schedule of a Pipeline, avoiding the holidays of `calendar`
+    /// when the Pipeline is on it
+    pub fn for_pipeline(pipeline: &Pipeline, calendar: Option<&Calendar>) -> Result<Self, String> {
+        let schedule = Self::parse(
+            &pipeline.schedule,
+            pipeline.timezone.as_deref(),
+            pipeline.start_date.as_deref(),
+            pipeline.end_date.as_deref(),
+        )?;
+        match (&pipeline.calendar, calendar) {
+            (None, _) => Ok(schedule),
+            (Some(id), Some(calendar)) if *id == calendar.id => {
+                schedule.with_holidays(&calendar.holidays, pipeline.holiday_policy)
+            }
+            (Some(id), _) => Err(format!("Calendar '{}' not found!", id)),
+        }
+    }
+
+    /// Avoid firing on any of the `YYYY-MM-DD` holidays in the schedule's time zone
+    pub fn with_holidays(
+        mut self,
+        holidays: &[String],
+        policy: HolidayPolicy,
+    ) -> Result<Self, String> {
+        let holidays = holidays
+            .iter()
+            .map(|date| calendar::parse_date(date))
+            .collect::<Result<_, String>>()?;
+        self.holidays = Some((holidays, policy));
+        Ok(self)
+    }
+
+    /// Return the first time strictly after `after` that the schedule fires,
+    /// or nothing once it has passed its end.
+    ///
+    /// Cron fields are matched against the wall clock in the schedule's time
+    /// zone. A wall clock time skipped by a DST change fires as soon as the
+    /// clock has jumped forward, and one that happens twice only fires the
+    /// first time. A time on a holiday is either skipped, or moved to the same
+    /// time on the next business day.
+    pub fn next_fire_time(&self, after: &DateTime<Utc>) -> Result<Option<DateTime<Utc>>, String> {
+        let Some((holidays, policy)) = &self.holidays else {
+            return self.next_scheduled_time(after);
+        };
+
+        // A time moved off an earlier holiday may still be ahead, so look from
+        // the start of the days off leading up to `after`
+        let mut from = *after;
+        if *policy == HolidayPolicy::NextBusinessDay {
+            let mut date = after.with_timezone(&self.timezone).date_naive();
+            while !self.is_business_day(date - Duration::days(1)) {
+                date -= Duration::days(1);
+            }
+            from = from.min(from_local(
+                self.timezone,
+                date.and_hms_opt(0, 0, 0).unwrap(),
+            ));
+        }
+
+        // Times moved to a business day can land after later scheduled times,
+        // so keep going until no earlier fire time is left
+        let mut next: Option<DateTime<Utc>> = None;
+        let mut current = self.next_scheduled_time(&from)?;
+        let mut checked = 0;
+        while let Some(scheduled) =
+            current.filter(|scheduled| next.is_none_or(|next| *scheduled < next))
+        {
+            checked += 1;
+            if checked > MAX_FIRE_TIMES {
+                return Err(format!(
+                    "Found no fire time that isn't on a holiday within {} tries!",
+                    MAX_FIRE_TIMES
+                ));
+            }
+            let local = scheduled.with_timezone(&self.timezone).naive_local();
+            if !holidays.contains(&local.date()) {
+                if scheduled > *after {
+                    next = Some(scheduled);
+                }
+                current = self.next_scheduled_time(&scheduled)?;
+                continue;
+            }
+            match policy {
+                HolidayPolicy::SkipOnHoliday => {
+                    // Skip the rest of the holiday in one go
+                    let next_day = local.date() + Duration::days(1);
+                    let end_of_day =
+                        from_local(self.timezone, next_day.and_hms_opt(0, 0, 0).unwrap())
+                            - Duration::seconds(1);
+                    current = self.next_scheduled_time(&end_of_day.max(scheduled))?;
+                }
+                HolidayPolicy::NextBusinessDay => {
+                    let mut date = local.date() + Duration::days(1);
+                    while !self.is_business_day(date) {
+                        date += Duration::days(1);
+                    }
+                    let moved = from_local(self.timezone, date.and_time(local.time()));
+                    if moved > *after {
+                        next = Some(next.map_or(moved, |next| next.min(moved)));
+                    }
+                    current = self.next_scheduled_time(&scheduled)?;
+                }
+            }
+        }
+        Ok(next)
+    }
+
+    /// Whether a day is a weekday that isn't one of the schedule's holidays
+    fn is_business_day(&self, date: NaiveDate) -> bool {
+        let is_holiday = self
+            .holidays
+            .as_ref()
+            .is_some_and(|(holidays, _)| holidays.contains(&date));
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !is_holiday
+    }
+
+    /// Return the first time strictly after `after` that the schedule would
+    /// fire, regardless of holidays
+    fn next_scheduled_time(&self, after: &DateTime<Utc>) -> Result<Option<DateTime<Utc>>, String> {
+        // Step back a second from the start so that the start itself can fire
+        let after = match self.start {
+            Some(start) if start > *after => start - Duration::seconds(1),
+            _ => *after,
+        };
+        let next = match &self.recurrence {
+            Recurrence::Cron { fields, seconds } => self.next_cron_time(fields, seconds, &after)?,
+            Recurrence::Interval(interval) => {
+                let anchor = self.start.unwrap_or(Utc.timestamp_opt(0, 0).unwrap());
+                if after < anchor {
+                    anchor
+                } else {
+                    let interval = interval.num_seconds();
+                    let elapsed = (after - anchor).num_seconds() / interval + 1;
+                    anchor + Duration::seconds(elapsed * interval)
+                }
+            }
+        };
+        Ok(Some(next).filter(|next| self.end.is_none_or(|end| *next <= end)))
+    }
+
+    fn next_cron_time(
+        &self,
+        fields: &str,
+        seconds: &BTreeSet<u32>,
+        after: &DateTime<Utc>,
+    ) -> Result<DateTime<Utc>, String> {
+        let mut local_after = after.with_timezone(&self.timezone).naive_local();
+        loop {
+            let local_next = next_cron_match(fields, seconds, local_after)?;
+            match self.timezone.from_local_datetime(&local_next) {
+                LocalResult::Single(next) => return Ok(next.with_timezone(&Utc)),
+                LocalResult::Ambiguous(earliest, _) if earliest.with_timezone(&Utc) > *after => {
+                    return Ok(earliest.with_timezone(&Utc))
+                }
+                // The first occurrence has passed, so move on to the next match
+                LocalResult::Ambiguous(..) => local_after = local_next,
+                LocalResult::None => return Ok(end_of_gap(self.timezone, local_next)),
+            }
+        }
+    }
+
+    /// Return up to `count` of the next times that the schedule fires after `after`
+    pub fn next_fire_times(
+        &self,
+        after: &DateTime<Utc>,
+        count: usize,
+    ) -> Result<Vec<DateTime<Utc>>, String> {
+        let mut fire_times = Vec::new();
+        let mut current = self.next_fire_time(after)?;
+        while let Some(fire_time) = current.filter(|_| fire_times.len() < count) {
+            fire_times.push(fire_time);
+            current = self.next_fire_time(&fire_time)?;
+        }
+        Ok(fire_times)
+    }
+
+    /// Enumerate every time the schedule fires between `start` and `end`, inclusive
+    pub fn fire_times_between(
+        &self,
+        start: &DateTime<Utc>,
+        end: &DateTime<Utc>,
+    ) -> Result<Vec<DateTime<Utc>>, String> {
+        if start > end {
+            return Err(format!("Start '{}' is after end '{}'!", start, end));
+        }
+
+        let mut fire_times = Vec::new();
+        // Step back a second so that a fire time exactly at `start` is included
+        let mut current = self.next_fire_time(&(*start - Duration::seconds(1)))?;
+        while let Some(fire_time) = current.filter(|fire_time| fire_time <= end) {
+            if fire_times.len() == MAX_FIRE_TIMES {
+                return Err(format!(
+                    "Range contains more than {} fire times!",
+                    MAX_FIRE_TIMES
+                ));
+            }
+            fire_times.push(fire_time);
+            current = self.next_fire_time(&fire_time)?;
+        }
+        Ok(fire_times)
+    }
+}
+
+/// Parse the part of a schedule that says how often it fires
+fn parse_recurrence(schedule: &str) -> Result<Recurrence, String> {
+    let schedule = schedule.trim();
+    if let Some(interval) = schedule.strip_prefix(EVERY_PREFIX) {
+        return parse_interval(interval.trim()).map(Recurrence::Interval);
+    }
+    let expression = match schedule {
+        "@yearly" | "@annually" => "0 0 1 1 *",
+        "@monthly" => "0 0 1 * *",
+        "@weekly" => "0 0 * * 0",
+        "@daily" | "@midnight" => "0 0 * * *",
+        "@hourly" => "0 * * * *",
+        _ if schedule.starts_with('@') => {
+            return Err(format!("Unknown schedule preset '{}'!", schedule))
+        }
+        _ => schedule,
+    };
+    let invalid = |e| format!("Invalid schedule '{}': {}", schedule, e);
+
+    // The parser indexes the fields directly, so guard against short expressions
+    let fields: Vec<&str> = expression.split_whitespace().collect();
+    let (seconds, fields) = match fields.len() {
+        5 => (BTreeSet::from([0]), fields.join(" ")),
+        6 => (
+            parse_field(fields[0], 0, 59).map_err(invalid)?,
+            fields[1..].join(" "),
+        ),
+        _ => {
+            return Err(format!(
+                "Schedule '{}' must have either 5 or 6 fields!",
+                schedule
+            ))
+        }
+    };
+    // The parser only reports invalid fields once it is used
+    parse(&fields, &Utc::now()).map_err(invalid)?;
+    Ok(Recurrence::Cron { fields, seconds })
+}
+
+/// Parse an interval such as `90s`, `15m` or `1h30m`
+fn parse_interval(interval: &str) -> Result<Duration, String> {
+    let invalid = || {
+        format!(
+            "Invalid interval '{}'! Use e.g. 90s, 15m, 1h30m or 1d",
+            interval
+        )
+    };
+    let mut seconds: i64 = 0;
+    let mut digits = String::new();
+    for character in interval.chars() {
+        if character.is_ascii_digit() {
+            digits.push(character);
+            continue;
+        }
+        let unit = match character {
+            'd' => 86_400,
+            'h' => 3_600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(invalid()),
+        };
+        let amount: i64 = digits.parse().map_err(|_| invalid())?;
+        seconds = amount
+            .checked_mul(unit)
+            .and_then(|amount| seconds.checked_add(amount))
+            .ok_or_else(invalid)?;
+        digits.clear();
+    }
+    // Durations are stored in milliseconds, so keep well clear of overflowing
+    if !digits.is_empty() || !(1..=i64::MAX / 1_000).contains(&seconds) {
+        return Err(invalid());
+    }
+    Ok(Duration::seconds(seconds))
+}
+
+/// Parse a start or end date, where a bare date covers the whole day
+fn parse_bound(bound: &str, timezone: Tz, is_end: bool) -> Result<DateTime<Utc>, String> {
+    let Ok(date) = NaiveDate::parse_from_str(bound, "%Y-%m-%d") else {
+        return parse_timestamp(bound).map_err(|_| {
+            format!(
+                "Invalid date '{}'! Use YYYY-MM-DD or an RFC 3339 timestamp",
+                bound
+            )
+        });
+    };
+    let local = if is_end {
+        date.and_hms_opt(23, 59, 59).unwrap()
+    } else {
+        date.and_hms_opt(0, 0, 0).unwrap()
+    };
+    match timezone.from_local_datetime(&local) {
+        LocalResult::Single(time) => Ok(time.with_timezone(&Utc)),
+        LocalResult::Ambiguous(earliest, latest) => {
+            Ok(if is_end { latest } else { earliest }.with_timezone(&Utc))
+        }
+        LocalResult::None => Ok(end_of_gap(timezone, local)),
+    }
+}
+
+/// Find the first wall clock time strictly after `after` that a cron
+/// expression matches, firing at any of `seconds` within each matching minute
+fn next_cron_match(
+    fields: &str,
+    seconds: &BTreeSet<u32>,
+    after: NaiveDateTime,
+) -> Result<NaiveDateTime, String> {
+    // The parser works on minutes in UTC, so the wall clock is passed in as UTC
+    let next_minute = |after: NaiveDateTime| {
+        parse(fields, &Utc.from_utc_datetime(&after))
+            .map(|next| next.naive_utc())
+            .map_err(|e| format!("Invalid schedule '{}': {}", fields, e))
+    };
+    let minute = after.with_second(0).unwrap().with_nanosecond(0).unwrap();
+
+    // A later second within the same minute, if that minute matches
+    if let Some(&second) = seconds.range(after.second() + 1..).next() {
+        if next_minute(minute - Duration::minutes(1))? == minute {
+            return Ok(minute + Duration::seconds(second.into()));
+        }
+    }
+    let first_second = seconds.first().copied().unwrap_or(0);
+    Ok(next_minute(after)? + Duration::seconds(first_second.into()))
+}
+
+/// Find the first time that the clock in `timezone` shows a wall clock time,
+/// or when it jumps past the time if it is skipped
+fn from_local(timezone: Tz, local: NaiveDateTime) -> DateTime<Utc> {
+    match timezone.from_local_datetime(&local).earliest() {
+        Some(time) => time.with_timezone(&Utc),
+        None => end_of_gap(timezone, local),
+    }
+}
+
+/// Find when the clock in `timezone` jumps past a wall clock time that it skips
+fn end_of_gap(timezone: Tz, skipped: NaiveDateTime) -> DateTime<Utc> {
+    let mut local = skipped.with_second(0).unwrap();
+    loop {
+        local += Duration::minutes(1);
+        if let Some(time) = timezone.from_local_datetime(&local).earliest() {
+            return time.with_timezone(&Utc);
+        }
+    }
+}
+
+/// Parse the IANA name of the time zone that a schedule is evaluated in,
+/// falling back to UTC when there is none
+pub fn parse_timezone(timezone: Option<&str>) -> Result<Tz, String> {
+    match timezone {
+        Some(timezone) => timezone
+            .parse()
+            .map_err(|_| format!("Unknown timezone '{}'!", timezone)),
+        None => Ok(Tz::UTC),
+    }
+}
+
+/// Format a timestamp as RFC 3339 so that timestamps sort chronologically as text
+pub fn format_timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Parse an RFC 3339 timestamp into UTC
+pub fn parse_timestamp(timestamp: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|datetime| datetime.with_timezone(&Utc))
+        .map_err(|e| format!("Invalid timestamp '{}': {}", timestamp, e))
+}
+
+/// Show an RFC 3339 timestamp on the wall clock of a time zone, for display
+pub fn format_local_timestamp(timestamp: &str, timezone: Option<&str>) -> String {
+    match (parse_timestamp(timestamp), parse_timezone(timezone)) {
+        (Ok(timestamp), Ok(timezone)) => timestamp
+            .with_timezone(&timezone)
+            .format("%Y-%m-%d %H:%M:%S %Z")
+            .to_string(),
+        _ => timestamp.to_string(),
+    }
+}
@@ -0,0 +1,301 @@
+use crate::process::{self, OutputLine};
+use chrono::{Duration as ChronoDuration, Utc};
+use sqlx::{self, Pool, Sqlite};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use synth_common::config::{self, ExecutorSettings};
+use synth_common::models::{TaskInstance, TaskStatus};
+use synth_common::{database, queries, schedule};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tokio::sync::{oneshot, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
+use tokio::time::Duration;
+use tracing::{error, info, span, Level};
+use uuid::Uuid;
+
+/// Seconds to wait before looking for work again when nothing was queued
+const CLAIM_POLL_INTERVAL: u64 = 1;
+
+/// Seconds between checks for cancelled TaskInstances
+const CANCEL_POLL_INTERVAL: u64 = 1;
+
+/// Handles to stop the processes of running TaskInstances, by TaskInstance ID
+pub(crate) type RunningTasks = Arc<Mutex<HashMap<String, oneshot::Sender<()>>>>;
+
+/// Aborts a spawned task once dropped, so that it can't outlive whatever
+/// spawned it, even if that panics
+pub(crate) struct AbortOnDrop<T>(pub(crate) JoinHandle<T>);
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+pub(crate) async fn async_sleep(sleep_secs: u64) {
+    let sleep_duration = Duration::from_secs(sleep_secs);
+    tokio::time::sleep(sleep_duration).await;
+}
+
+/// Append a TaskInstance's output lines to the database as they arrive
+async fn write_task_logs(
+    task_instance_id: String,
+    mut receiver: UnboundedReceiver<OutputLine>,
+    db_pool: Pool<Sqlite>,
+) {
+    while let Some(task_logs) = process::next_task_logs(&task_instance_id, &mut receiver).await {
+        if let Err(e) = queries::insert_task_logs(&task_logs, &db_pool).await {
+            error!("Failed to save logs for '{}'! {}", task_instance_id, e);
+        }
+    }
+}
+
+/// Stop the processes of TaskInstances that were cancelled through the API
+async fn watch_cancellations(running_tasks: RunningTasks, db_pool: Pool<Sqlite>) {
+    loop {
+        match queries::select_unfinished_cancelled_task_instance_ids(&db_pool).await {
+            Ok(task_instance_ids) => {
+                let mut running_tasks = running_tasks.lock().unwrap();
+                for task_instance_id in task_instance_ids {
+                    if let Some(cancel) = running_tasks.remove(&task_instance_id) {
+                        info!("Cancelling Task Instance '{}'!", task_instance_id);
+                        let _ = cancel.send(());
+                    }
+                }
+            }
+            Err(e) => error!("Failed to check for cancelled Task Instances! {}", e),
+        }
+        async_sleep(CANCEL_POLL_INTERVAL).await;
+    }
+}
+
+/// Time until which a lease taken now is held
+fn lease_expires_at(lease_duration: u64) -> String {
+    let lease_duration = ChronoDuration::seconds(i64::try_from(lease_duration).unwrap_or(i64::MAX));
+    schedule::format_timestamp(&(Utc::now() + lease_duration))
+}
+
+/// Send heartbeats for a running TaskInstance, renewing its lease.
+///
+/// If the lease was lost, e.g. because the TaskInstance was marked as a zombie
+/// while this executor was unresponsive, its process is stopped.
+async fn keep_lease(
+    task_instance_id: String,
+    executor_id: String,
+    lease_duration: u64,
+    db_pool: Pool<Sqlite>,
+    running_tasks: RunningTasks,
+) {
+    // Renew well before the lease expires, so a slow renewal doesn't lose it
+    let renew_interval = (lease_duration / 3).max(1);
+    loop {
+        async_sleep(renew_interval).await;
+        let renewed = queries::renew_task_instance_lease(
+            &task_instance_id,
+            &executor_id,
+            &schedule::format_timestamp(&Utc::now()),
+            &lease_expires_at(lease_duration),
+            &db_pool,
+        )
+        .await;
+        match renewed {
+            Ok(true) => {}
+            Ok(false) => {
+                if let Some(cancel) = running_tasks.lock().unwrap().remove(&task_instance_id) {
+                    error!("Lost the lease on '{}'! Stopping it.", task_instance_id);
+                    let _ = cancel.send(());
+                }
+                return;
+            }
+            Err(e) => error!("Failed to renew the lease on '{}'! {}", task_instance_id, e),
+        }
+    }
+}
+
+/// Run a claimed TaskInstance and record its outcome.
+///
+/// Retrying a failed TaskInstance is left to the scheduler.
+async fn run_task_instance(
+    task_instance: TaskInstance,
+    executor_id: String,
+    lease_duration: u64,
+    db_pool: Pool<Sqlite>,
+    running_tasks: RunningTasks,
+    _task_slot: OwnedSemaphorePermit,
+) {
+    let task_instance_id = task_instance.id.clone();
+    let assignment = match queries::select_task_assignment(task_instance, &db_pool).await {
+        Ok(assignment) => assignment,
+        Err(e) => {
+            error!("Task Instance '{}' can't be run! {}", task_instance_id, e);
+            fail_task_instance(&task_instance_id, &executor_id, &db_pool).await;
+            return;
+        }
+    };
+    let task_instance = &assignment.task_instance;
+    info!(
+        "Task '{}' for Pipeline '{}' has started! (attempt {}/{})",
+        task_instance.task_id,
+        task_instance.pipeline_id,
+        task_instance.attempt,
+        task_instance.last_attempt
+    );
+    // Stops renewing the lease however this function ends, so that a
+    // TaskInstance whose run panicked becomes a zombie
+    let _lease_keeper = AbortOnDrop(tokio::spawn(keep_lease(
+        task_instance_id.clone(),
+        executor_id.clone(),
+        lease_duration,
+        db_pool.clone(),
+        running_tasks.clone(),
+    )));
+
+    // Run the Task subprocess, appending its output as it is produced
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let log_writer = tokio::spawn(write_task_logs(
+        task_instance_id.clone(),
+        receiver,
+        db_pool.clone(),
+    ));
+    let (cancel_sender, cancel_receiver) = oneshot::channel();
+    running_tasks
+        .lock()
+        .unwrap()
+        .insert(task_instance_id.clone(), cancel_sender);
+    let result = process::run_task_command(
+        &assignment.command,
+        assignment.timeout.map(u64::from),
+        &assignment.params,
+        sender,
+        cancel_receiver,
+    )
+    .await;
+    running_tasks.lock().unwrap().remove(&task_instance_id);
+    if let Err(e) = log_writer.await {
+        error!("Failed to save logs for '{}'! {}", task_instance_id, e);
+    }
+    let result = match result {
+        Ok(result) => result,
+        Err(e) => {
+            error!("Task '{}' failed to run! {}", task_instance.task_id, e);
+            fail_task_instance(&task_instance_id, &executor_id, &db_pool).await;
+            return;
+        }
+    };
+    let execution_end = schedule::format_timestamp(&Utc::now());
+
+    let status = result.task_status();
+    let exit_code = result.exit_code();
+    info!("Saving to database...");
+    let finished = queries::finish_task_instance(
+        &task_instance_id,
+        &executor_id,
+        status,
+        exit_code,
+        &execution_end,
+        &db_pool,
+    )
+    .await;
+
+    match finished {
+        Ok(true) if status == TaskStatus::Success => {
+            info!("Task '{}' succeeded!", task_instance.task_id)
+        }
+        Ok(true) => error!("Task '{}' failed!", task_instance.task_id),
+        // The TaskInstance is no longer running because it was cancelled, or
+        // its lease expired and it was marked as a zombie
+        Ok(false) => {
+            let result = queries::finish_cancelled_task_instance(
+                &task_instance_id,
+                &executor_id,
+                exit_code,
+                &execution_end,
+                &db_pool,
+            )
+            .await;
+            match result {
+                Ok(()) => info!("Task '{}' was cancelled!", task_instance.task_id),
+                Err(e) => error!("Failed to save '{}'! {}", task_instance_id, e),
+            }
+        }
+        Err(e) => error!("Failed to save '{}'! {}", task_instance_id, e),
+    }
+}
+
+/// Record that a claimed TaskInstance failed without its command running.
+///
+/// If this fails too, the lease runs out and the TaskInstance becomes a zombie.
+async fn fail_task_instance(task_instance_id: &str, executor_id: &str, db_pool: &Pool<Sqlite>) {
+    let execution_end = schedule::format_timestamp(&Utc::now());
+    let result = queries::finish_task_instance(
+        task_instance_id,
+        executor_id,
+        TaskStatus::Failed,
+        None,
+        &execution_end,
+        db_pool,
+    )
+    .await;
+    if let Err(e) = result {
+        error!("Failed to save '{}'! {}", task_instance_id, e);
+    }
+}
+
+pub async fn run_executor() {
+    let span = span!(Level::INFO, "Executor");
+    let _enter = span.enter();
+    let settings = config::load_config("synth.toml").expect("Failed to load the config!");
+    let db_pool = database::get_db_pool().await;
+    execute_task_instances(&settings.executor, db_pool).await;
+}
+
+/// Claim queued TaskInstances and run them, never running more than
+/// `max_concurrent_tasks` at the same time
+pub async fn execute_task_instances(settings: &ExecutorSettings, db_pool: Pool<Sqlite>) {
+    let lease_duration = settings.lease_duration;
+    // Identifies this executor as the holder of its leases
+    let executor_id = Uuid::new_v4().to_string();
+    // Shared by every Task, limiting how many run at the same time
+    let task_slots = Arc::new(Semaphore::new(settings.max_concurrent_tasks));
+    let running_tasks = RunningTasks::default();
+    tokio::spawn(watch_cancellations(running_tasks.clone(), db_pool.clone()));
+    info!("Executor '{}' is waiting for Tasks!", executor_id);
+
+    // This infinite loop is the executor
+    loop {
+        // Only claim work when there's a free slot to run it in
+        let task_slot = task_slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("Task slots were closed!");
+        let now = schedule::format_timestamp(&Utc::now());
+        let claimed = queries::claim_task_instance(
+            &executor_id,
+            &now,
+            &lease_expires_at(lease_duration),
+            &db_pool,
+        )
+        .await;
+        match claimed {
+            Ok(Some(task_instance)) => {
+                tokio::spawn(run_task_instance(
+                    task_instance,
+                    executor_id.clone(),
+                    lease_duration,
+                    db_pool.clone(),
+                    running_tasks.clone(),
+                    task_slot,
+                ));
+            }
+            Ok(None) => {
+                drop(task_slot);
+                async_sleep(CLAIM_POLL_INTERVAL).await;
+            }
+            Err(e) => {
+                error!("Failed to claim a Task Instance! {}", e);
+                drop(task_slot);
+                async_sleep(CLAIM_POLL_INTERVAL).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use synth_common::config::WorkerSettings;
+use synth_common::telemetry;
+pub mod executor;
+mod process;
+mod worker;
+
+/// The Entrypoint for the Executor.
+pub async fn start() {
+    telemetry::init_logging();
+    executor::run_executor().await;
+}
+
+/// The Entrypoint for a remote Worker, which runs Tasks through the API of
+/// the server at `server_url` instead of through the database.
+pub async fn start_worker(server_url: String, settings: WorkerSettings) {
+    telemetry::init_logging();
+    worker::run_worker(server_url, settings).await;
+}
@@ -0,0 +1,4 @@
+#[tokio::main]
+async fn main() {
+    synth_executor::start().await;
+}
@@ -0,0 +1,201 @@
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::io;
+use std::process::{ExitStatus, Stdio};
+use synth_common::models::{TaskLog, TaskStatus};
+use synth_common::schedule;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::process::Child;
+use tokio::process::Command;
+use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
+use tokio::sync::oneshot;
+use tokio::time::{self, Duration};
+use tracing::warn;
+
+/// Seconds a timed out or cancelled Task has to exit after SIGTERM before it is killed
+const KILL_GRACE_PERIOD: u64 = 10;
+
+/// Upper bound on the number of log lines saved or sent at once
+const MAX_LOG_BATCH: usize = 500;
+
+/// A single line of output from a Task
+pub struct OutputLine {
+    pub timestamp: DateTime<Utc>,
+    /// Either `stdout` or `stderr`
+    pub stream: &'static str,
+    pub line: String,
+}
+
+/// Wait for a TaskInstance's next output lines, batched up with any others
+/// that are already waiting.
+///
+/// Returns `None` once the Task's output is closed.
+pub async fn next_task_logs(
+    task_instance_id: &str,
+    receiver: &mut UnboundedReceiver<OutputLine>,
+) -> Option<Vec<TaskLog>> {
+    let to_task_log = |output_line: OutputLine| TaskLog {
+        task_instance_id: task_instance_id.to_string(),
+        timestamp: schedule::format_timestamp(&output_line.timestamp),
+        stream: output_line.stream.to_string(),
+        line: output_line.line,
+        ..Default::default()
+    };
+    let mut task_logs = vec![to_task_log(receiver.recv().await?)];
+    while task_logs.len() < MAX_LOG_BATCH {
+        let Ok(output_line) = receiver.try_recv() else {
+            break;
+        };
+        task_logs.push(to_task_log(output_line));
+    }
+    Some(task_logs)
+}
+
+/// The result of running a Task's command
+pub struct TaskOutput {
+    pub status: ExitStatus,
+    pub timed_out: bool,
+    pub cancelled: bool,
+}
+impl TaskOutput {
+    pub fn success(&self) -> bool {
+        !self.timed_out && !self.cancelled && self.status.success()
+    }
+
+    /// The status that the TaskInstance ends with
+    pub fn task_status(&self) -> TaskStatus {
+        if self.timed_out {
+            TaskStatus::TimedOut
+        } else if self.success() {
+            TaskStatus::Success
+        } else {
+            TaskStatus::Failed
+        }
+    }
+
+    /// Exit code of the command, unset if it was killed by a signal
+    pub fn exit_code(&self) -> Option<i64> {
+        self.status.code().map(i64::from)
+    }
+}
+
+/// Send a signal to every process in a Task's process group
+fn signal_process_group(pid: u32, signal: libc::c_int) {
+    // The Task's shell leads its own process group, so a negative PID
+    // targets the shell and everything that it spawned
+    unsafe {
+        libc::kill(-(pid as libc::pid_t), signal);
+    }
+}
+
+/// Stop a Task with SIGTERM, escalating to SIGKILL if it outlives the grace period
+async fn terminate(child: &mut Child, pid: u32) -> io::Result<ExitStatus> {
+    signal_process_group(pid, libc::SIGTERM);
+    let grace_period = Duration::from_secs(KILL_GRACE_PERIOD);
+    match time::timeout(grace_period, child.wait()).await {
+        Ok(status) => status,
+        Err(_) => {
+            warn!("Task ignored SIGTERM! Killing.");
+            signal_process_group(pid, libc::SIGKILL);
+            child.wait().await
+        }
+    }
+}
+
+/// Read a stream line by line as the Task produces it, timestamping each line
+fn spawn_reader<R>(stream: R, name: &'static str, sender: UnboundedSender<OutputLine>)
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut reader = BufReader::new(stream);
+        let mut buffer = Vec::new();
+        loop {
+            buffer.clear();
+            match reader.read_until(b'\n', &mut buffer).await {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    // Tasks may print anything, so invalid UTF-8 is replaced
+                    let line = String::from_utf8_lossy(&buffer);
+                    let output_line = OutputLine {
+                        timestamp: Utc::now(),
+                        stream: name,
+                        line: line.trim_end_matches(['\n', '\r']).to_string(),
+                    };
+                    if sender.send(output_line).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// Run a Task's command in its own process group.
+///
+/// Stdout and stderr lines are sent to `output` as they are read, interleaved
+/// in the order they were produced. The channel closes once both are exhausted.
+/// Run parameters are exposed to the command as `SYNTH_PARAM_<name>` environment variables.
+///
+/// If the timeout elapses or `cancel` fires first, the process group is sent
+/// SIGTERM, followed by SIGKILL after a grace period. Fails if the command
+/// can't be started or waited on.
+pub async fn run_task_command(
+    task_command: &str,
+    timeout: Option<u64>,
+    params: &HashMap<String, String>,
+    output: UnboundedSender<OutputLine>,
+    mut cancel: oneshot::Receiver<()>,
+) -> io::Result<TaskOutput> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(task_command)
+        .envs(
+            params
+                .iter()
+                .map(|(name, value)| (format!("SYNTH_PARAM_{}", name), value)),
+        )
+        .process_group(0)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let pid = child
+        .id()
+        .ok_or_else(|| io::Error::other("Task exited before it was tracked!"))?;
+    // Both readers share a channel, which keeps their lines in order
+    if let Some(stdout) = child.stdout.take() {
+        spawn_reader(stdout, "stdout", output.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        spawn_reader(stderr, "stderr", output);
+    }
+
+    let deadline = async {
+        match timeout {
+            Some(timeout) => time::sleep(Duration::from_secs(timeout)).await,
+            None => std::future::pending().await,
+        }
+    };
+    let mut timed_out = false;
+    let mut cancelled = false;
+    let status = tokio::select! {
+        status = child.wait() => status,
+        _ = deadline => {
+            timed_out = true;
+            warn!("Task timed out after {} second(s)! Terminating.", timeout.unwrap_or_default());
+            terminate(&mut child, pid).await
+        }
+        // A dropped sender means the Task can no longer be cancelled
+        Ok(()) = &mut cancel => {
+            cancelled = true;
+            warn!("Task was cancelled! Terminating.");
+            terminate(&mut child, pid).await
+        }
+    }?;
+
+    Ok(TaskOutput {
+        status,
+        timed_out,
+        cancelled,
+    })
+}
@@ -0,0 +1,338 @@
+use crate::executor::{async_sleep, RunningTasks};
+use crate::process::{self, OutputLine};
+use anyhow::anyhow;
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::sync::Arc;
+use synth_common::config::WorkerSettings;
+use synth_common::models::{
+    RegisterWorker, TaskAssignment, TaskLog, TaskResult, TaskStatus, Worker, WorkerHeartbeat,
+};
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tokio::sync::{oneshot, OwnedSemaphorePermit, Semaphore};
+use tracing::{error, info, span, warn, Level};
+
+/// Seconds that the server is asked to wait for a queued Task on every poll
+const POLL_WAIT: u64 = 20;
+
+/// Seconds to wait before calling the server again after a failed call
+const RETRY_INTERVAL: u64 = 5;
+
+/// The data of a response from the API
+#[derive(Deserialize)]
+struct ApiResponse<T> {
+    data: Option<Vec<T>>,
+}
+
+/// Calls the worker endpoints of the API on behalf of a registered Worker
+struct WorkerClient {
+    client: Client,
+    server_url: String,
+    worker_id: String,
+}
+impl WorkerClient {
+    /// Register with the server as a new Worker
+    async fn register(server_url: String, hostname: String) -> Result<Self, anyhow::Error> {
+        let client = Client::new();
+        let url = format!("{}/api/workers", server_url);
+        let body = RegisterWorker { hostname };
+        let response = client.post(&url).json(&body).send().await?;
+        let worker: Worker = read_data(response)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("The server didn't return the registered worker!"))?;
+        Ok(Self {
+            client,
+            server_url,
+            worker_id: worker.id,
+        })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/api/workers/{}{}", self.server_url, self.worker_id, path)
+    }
+
+    /// Keep the Worker's leases alive, getting back the TaskInstances to stop
+    async fn heartbeat(&self, running: Vec<String>) -> Result<Vec<String>, reqwest::Error> {
+        let body = WorkerHeartbeat { running };
+        let response = self
+            .client
+            .post(self.url("/heartbeat"))
+            .json(&body)
+            .send()
+            .await?;
+        read_data(response).await
+    }
+
+    /// Wait for a queued Task, claiming it for this Worker
+    async fn poll(&self) -> Result<Option<TaskAssignment>, reqwest::Error> {
+        let response = self
+            .client
+            .get(self.url("/task"))
+            .query(&[("wait", POLL_WAIT)])
+            .send()
+            .await?;
+        Ok(read_data(response).await?.into_iter().next())
+    }
+
+    async fn send_logs(
+        &self,
+        task_instance_id: &str,
+        task_logs: &[TaskLog],
+    ) -> Result<(), reqwest::Error> {
+        let path = format!("/task_instances/{}/logs", task_instance_id);
+        self.client
+            .post(self.url(&path))
+            .json(task_logs)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    async fn report_result(
+        &self,
+        task_instance_id: &str,
+        result: &TaskResult,
+    ) -> Result<(), reqwest::Error> {
+        let path = format!("/task_instances/{}/result", task_instance_id);
+        self.client
+            .post(self.url(&path))
+            .json(result)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Read the data of a successful response
+async fn read_data<T>(response: reqwest::Response) -> Result<Vec<T>, reqwest::Error>
+where
+    T: DeserializeOwned,
+{
+    let response: ApiResponse<T> = response.error_for_status()?.json().await?;
+    Ok(response.data.unwrap_or_default())
+}
+
+/// Name of the machine that the Worker runs on
+fn hostname() -> String {
+    let mut buffer = [0u8; 256];
+    let result = unsafe { libc::gethostname(buffer.as_mut_ptr().cast(), buffer.len()) };
+    if result != 0 {
+        return "unknown".to_string();
+    }
+    let length = buffer.iter().position(|&byte| byte == 0).unwrap_or(0);
+    String::from_utf8_lossy(&buffer[..length]).into_owned()
+}
+
+/// Send heartbeats, stopping the processes of TaskInstances that the server
+/// says this Worker no longer holds
+async fn send_heartbeats(
+    client: Arc<WorkerClient>,
+    heartbeat_interval: u64,
+    running_tasks: RunningTasks,
+) {
+    loop {
+        async_sleep(heartbeat_interval).await;
+        let running: Vec<String> = running_tasks.lock().unwrap().keys().cloned().collect();
+        match client.heartbeat(running).await {
+            Ok(task_instance_ids) => {
+                let mut running_tasks = running_tasks.lock().unwrap();
+                for task_instance_id in task_instance_ids {
+                    if let Some(cancel) = running_tasks.remove(&task_instance_id) {
+                        info!("Stopping Task Instance '{}'!", task_instance_id);
+                        let _ = cancel.send(());
+                    }
+                }
+            }
+            Err(e) => error!("Failed to send a heartbeat! {}", e),
+        }
+    }
+}
+
+/// Send a TaskInstance's output lines to the server as they arrive
+async fn send_task_logs(
+    client: Arc<WorkerClient>,
+    task_instance_id: String,
+    mut receiver: UnboundedReceiver<OutputLine>,
+) {
+    while let Some(task_logs) = process::next_task_logs(&task_instance_id, &mut receiver).await {
+        if let Err(e) = client.send_logs(&task_instance_id, &task_logs).await {
+            error!("Failed to send logs for '{}'! {}", task_instance_id, e);
+        }
+    }
+}
+
+/// Keeps a TaskInstance in the heartbeats until dropped, even if running it
+/// panics
+struct HeartbeatEntry {
+    task_instance_id: String,
+    running_tasks: RunningTasks,
+}
+impl Drop for HeartbeatEntry {
+    fn drop(&mut self) {
+        self.running_tasks
+            .lock()
+            .unwrap()
+            .remove(&self.task_instance_id);
+    }
+}
+
+/// Report how a TaskInstance ended, retrying until the server records it or
+/// refuses it.
+///
+/// Returns whether the server recorded the result.
+async fn report_task_result(
+    client: &WorkerClient,
+    task_instance_id: &str,
+    task_result: &TaskResult,
+) -> bool {
+    loop {
+        match client.report_result(task_instance_id, task_result).await {
+            Ok(()) => return true,
+            // The server no longer considers this Worker to be running it
+            Err(e) if e.status().is_some_and(|status| status.is_client_error()) => {
+                warn!(
+                    "The result of '{}' was refused by the server! {}",
+                    task_instance_id, e
+                );
+                return false;
+            }
+            Err(e) => {
+                error!(
+                    "Failed to report the result of '{}'! {}",
+                    task_instance_id, e
+                );
+                async_sleep(RETRY_INTERVAL).await;
+            }
+        }
+    }
+}
+
+/// Run a TaskInstance claimed from the server and report its outcome
+async fn run_assignment(
+    client: Arc<WorkerClient>,
+    assignment: TaskAssignment,
+    running_tasks: RunningTasks,
+    _task_slot: OwnedSemaphorePermit,
+) {
+    let task_instance = &assignment.task_instance;
+    let task_instance_id = task_instance.id.clone();
+    info!(
+        "Task '{}' for Pipeline '{}' has started! (attempt {}/{})",
+        task_instance.task_id,
+        task_instance.pipeline_id,
+        task_instance.attempt,
+        task_instance.last_attempt
+    );
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let log_sender = tokio::spawn(send_task_logs(
+        client.clone(),
+        task_instance_id.clone(),
+        receiver,
+    ));
+    let (cancel_sender, cancel_receiver) = oneshot::channel();
+    running_tasks
+        .lock()
+        .unwrap()
+        .insert(task_instance_id.clone(), cancel_sender);
+    // Its lease is renewed until the result is reported, so that the result
+    // isn't lost if the server is briefly unreachable
+    let _heartbeat_entry = HeartbeatEntry {
+        task_instance_id: task_instance_id.clone(),
+        running_tasks,
+    };
+    let result = process::run_task_command(
+        &assignment.command,
+        assignment.timeout.map(u64::from),
+        &assignment.params,
+        sender,
+        cancel_receiver,
+    )
+    .await;
+    if let Err(e) = log_sender.await {
+        error!("Failed to send logs for '{}'! {}", task_instance_id, e);
+    }
+
+    let task_result = match result {
+        Ok(result) => TaskResult {
+            status: result.task_status(),
+            exit_code: result.exit_code(),
+        },
+        Err(e) => {
+            error!("Task '{}' failed to run! {}", task_instance.task_id, e);
+            TaskResult {
+                status: TaskStatus::Failed,
+                exit_code: None,
+            }
+        }
+    };
+    if !report_task_result(&client, &task_instance_id, &task_result).await {
+        warn!(
+            "Task '{}' was stopped by the server!",
+            task_instance.task_id
+        );
+    } else if task_result.status == TaskStatus::Success {
+        info!("Task '{}' succeeded!", task_instance.task_id);
+    } else {
+        error!("Task '{}' failed!", task_instance.task_id);
+    }
+}
+
+pub async fn run_worker(server_url: String, settings: WorkerSettings) {
+    let span = span!(Level::INFO, "Worker");
+    let _enter = span.enter();
+    let client = loop {
+        match WorkerClient::register(server_url.clone(), hostname()).await {
+            Ok(client) => break Arc::new(client),
+            Err(e) => {
+                error!("Failed to register with '{}'! {}", server_url, e);
+                async_sleep(RETRY_INTERVAL).await;
+            }
+        }
+    };
+    info!(
+        "Worker '{}' is waiting for Tasks from '{}'!",
+        client.worker_id, server_url
+    );
+
+    // Shared by every Task, limiting how many run at the same time
+    let task_slots = Arc::new(Semaphore::new(settings.max_concurrent_tasks));
+    let running_tasks = RunningTasks::default();
+    tokio::spawn(send_heartbeats(
+        client.clone(),
+        settings.heartbeat_interval,
+        running_tasks.clone(),
+    ));
+
+    // This infinite loop is the worker
+    loop {
+        // Only claim work when there's a free slot to run it in
+        let task_slot = task_slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("Task slots were closed!");
+        match client.poll().await {
+            Ok(Some(assignment)) => {
+                tokio::spawn(run_assignment(
+                    client.clone(),
+                    assignment,
+                    running_tasks.clone(),
+                    task_slot,
+                ));
+            }
+            // The server already waited for work, so poll again right away
+            Ok(None) => drop(task_slot),
+            Err(e) => {
+                error!("Failed to poll for a Task! {}", e);
+                drop(task_slot);
+                async_sleep(RETRY_INTERVAL).await;
+            }
+        }
+    }
+}
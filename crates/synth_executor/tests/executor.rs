@@ -0,0 +1,210 @@
+use pretty_assertions::assert_eq;
+use sqlx::SqlitePool;
+use std::time::Duration;
+use synth_common::config::{self, BuildUrl, ExecutorSettings};
+use synth_common::database;
+use synth_common::models::{
+    Pipeline, PipelineRun, PipelineRunStatus, Task, TaskInstance, TaskStatus,
+};
+use synth_common::queries;
+use synth_executor::executor;
+use tokio::time::{self, Instant};
+use uuid::Uuid;
+
+/// Seconds that a test waits for its TaskInstances to finish
+const FINISH_TIMEOUT: u64 = 30;
+
+/// Create a migrated test database and return a pool for it
+async fn spawn_db() -> SqlitePool {
+    let mut config =
+        config::load_config("../../synth.toml").expect("Failed to load configuration!");
+    config.database.database = format!("test-{}", Uuid::new_v4());
+    let db_url = &config.database.build_url();
+    database::setupdb(db_url)
+        .await
+        .expect("Failed to setup test database!");
+    SqlitePool::connect(db_url)
+        .await
+        .expect("Failed to create the database pool!")
+}
+
+/// Register a Pipeline of `count` independent Tasks running `command`, and
+/// queue a TaskInstance of each within a running PipelineRun
+async fn queue_task_instances(
+    command: &str,
+    timeout: Option<u32>,
+    count: usize,
+    db_pool: &SqlitePool,
+) -> Vec<String> {
+    let pipeline = Pipeline {
+        id: "executorpipeline".to_owned(),
+        schedule: "0 * * * *".to_owned(),
+        ..Default::default()
+    };
+    queries::insert_pipeline(pipeline, db_pool)
+        .await
+        .expect("Failed to insert pipeline!");
+    let pipeline_run = PipelineRun {
+        id: "executorpipelinerun".to_owned(),
+        pipeline_id: "executorpipeline".to_owned(),
+        scheduled_time: "2024-01-08T09:00:00Z".to_owned(),
+        status: PipelineRunStatus::Running,
+        ..Default::default()
+    };
+    queries::insert_pipeline_run(&pipeline_run, db_pool)
+        .await
+        .expect("Failed to insert pipeline run!");
+
+    let mut task_instance_ids = Vec::new();
+    for index in 0..count {
+        let task = Task {
+            id: format!("executortask{}", index),
+            pipeline_id: "executorpipeline".to_owned(),
+            command: command.to_owned(),
+            timeout,
+            ..Default::default()
+        };
+        queries::upsert_task(&task, db_pool)
+            .await
+            .expect("Failed to insert task!");
+        let task_instance_id = format!("executortaskinstance{}", index);
+        queries::insert_task_instance(
+            TaskInstance {
+                id: task_instance_id.clone(),
+                task_id: task.id,
+                pipeline_id: "executorpipeline".to_owned(),
+                pipeline_run_id: Some("executorpipelinerun".to_owned()),
+                status: TaskStatus::Queued,
+                attempt: 1,
+                last_attempt: 1,
+                ..Default::default()
+            },
+            db_pool,
+        )
+        .await
+        .expect("Failed to insert task instance!");
+        task_instance_ids.push(task_instance_id);
+    }
+    task_instance_ids
+}
+
+/// Run an executor until none of the TaskInstances are queued or running,
+/// returning the most TaskInstances that were running at the same time
+async fn execute_until_finished(max_concurrent_tasks: usize, db_pool: &SqlitePool) -> usize {
+    let settings = ExecutorSettings {
+        max_concurrent_tasks,
+        ..Default::default()
+    };
+    let executor_pool = db_pool.clone();
+    let executor = tokio::spawn(async move {
+        executor::execute_task_instances(&settings, executor_pool).await;
+    });
+
+    let deadline = Instant::now() + Duration::from_secs(FINISH_TIMEOUT);
+    let mut most_running = 0;
+    loop {
+        let running = queries::select_task_instances(Some(TaskStatus::Running), db_pool)
+            .await
+            .unwrap()
+            .len();
+        let queued = queries::select_task_instances(Some(TaskStatus::Queued), db_pool)
+            .await
+            .unwrap()
+            .len();
+        most_running = most_running.max(running);
+        if running == 0 && queued == 0 {
+            break;
+        }
+        assert!(
+            Instant::now() < deadline,
+            "TaskInstances didn't finish in time!"
+        );
+        time::sleep(Duration::from_millis(100)).await;
+    }
+    executor.abort();
+    most_running
+}
+
+#[tokio::test]
+async fn executor_runs_at_most_max_concurrent_tasks() {
+    // Arrange
+    let db_pool = spawn_db().await;
+    let task_instance_ids = queue_task_instances("sleep 1", None, 6, &db_pool).await;
+
+    // Act
+    let most_running = execute_until_finished(2, &db_pool).await;
+
+    // Assert that Tasks ran side by side, but never more than allowed
+    assert_eq!(most_running, 2);
+    for task_instance_id in task_instance_ids {
+        let task_instance = queries::select_task_instance_by_id(&task_instance_id, &db_pool)
+            .await
+            .unwrap();
+        assert_eq!(task_instance.status, TaskStatus::Success);
+        assert_eq!(task_instance.exit_code, Some(0));
+        assert!(!task_instance.execution_end.is_empty());
+    }
+}
+
+/// Count the processes in a process group that haven't exited yet
+fn live_processes_in_group(process_group: i32) -> usize {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return 0;
+    };
+    entries
+        .filter_map(|entry| std::fs::read_to_string(entry.ok()?.path().join("stat")).ok())
+        .filter(|stat| {
+            // Fields after the command name are the state, the parent's PID
+            // and the process group
+            let Some((_, fields)) = stat.rsplit_once(')') else {
+                return false;
+            };
+            let fields: Vec<&str> = fields.split_whitespace().collect();
+            fields.len() > 2 && fields[0] != "Z" && fields[2] == process_group.to_string()
+        })
+        .count()
+}
+
+#[tokio::test]
+async fn timed_out_task_stops_its_process_group() {
+    // Arrange
+    let db_pool = spawn_db().await;
+    let task_instance_ids =
+        queue_task_instances("echo $$; sh -c 'sleep 60 & sleep 60'", Some(1), 1, &db_pool).await;
+
+    // Act
+    execute_until_finished(1, &db_pool).await;
+
+    // Assert that the shell and everything that it spawned is gone
+    let task_instance = queries::select_task_instance_by_id(&task_instance_ids[0], &db_pool)
+        .await
+        .unwrap();
+    assert_eq!(task_instance.status, TaskStatus::TimedOut);
+    let task_logs = queries::select_task_logs_by_task_instance_id(&task_instance.id, &db_pool)
+        .await
+        .unwrap();
+    let process_group: i32 = task_logs[0].line.parse().unwrap();
+    assert_eq!(live_processes_in_group(process_group), 0);
+}
+
+#[test]
+fn config_without_task_slots_fails() {
+    let config = std::fs::read_to_string("../../synth.toml").expect("Failed to read the config!");
+    for (section, setting) in [
+        ("[executor]", "max_concurrent_tasks = 256"),
+        ("[worker]", "max_concurrent_tasks = 4"),
+    ] {
+        assert!(config.contains(section) && config.contains(setting));
+        let filepath = std::env::temp_dir().join(format!("synth-{}.toml", Uuid::new_v4()));
+        std::fs::write(
+            &filepath,
+            config.replace(setting, "max_concurrent_tasks = 0"),
+        )
+        .unwrap();
+
+        let result = config::load_config(filepath.to_str().unwrap());
+        std::fs::remove_file(&filepath).unwrap();
+
+        assert!(result.is_err(), "Accepted 0 task slots in {}", section);
+    }
+}
@@ -1,5 +1,5 @@
 use synth_common::telemetry;
-mod scheduler;
+pub mod scheduler;
 
 /// The Entrypoint for the Scheduler.
 pub async fn start() {
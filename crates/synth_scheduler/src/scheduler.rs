@@ -1,129 +1,665 @@
-use chrono::{DateTime, Utc};
-use cron_parser::parse;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use rand::Rng;
 use sqlx::{self, Pool, Sqlite};
-use std::collections::HashMap;
-use std::process::{Command, Output, Stdio};
-use synth_common::models::{Pipeline, TaskInstance};
-use synth_common::{database, queries};
-use tracing::{error, info, span, Level};
+use std::collections::{HashMap, HashSet};
+use synth_common::config::{self, SchedulerSettings};
+use synth_common::models::{
+    LeaderLease, OverlapPolicy, Pipeline, PipelineRun, PipelineRunStatus, RetryBackoff, RunTrigger,
+    Task, TaskInstance, TaskStatus,
+};
+use synth_common::schedule::Schedule;
+use synth_common::{dag, database, queries, schedule};
+use tokio::time::Duration;
+use tracing::{debug, error, info, span, warn, Level};
+use uuid::Uuid;
+
+/// Upper bound, in seconds, on the delay between two attempts of a Task
+const MAX_RETRY_DELAY: u64 = 60 * 60;
 
 async fn async_sleep(sleep_secs: u64) {
-    let sleep_duration = tokio::time::Duration::from_secs(sleep_secs);
+    let sleep_duration = Duration::from_secs(sleep_secs);
     tokio::time::sleep(sleep_duration).await;
 }
 
-fn run_task_command(task_command: &str) -> Output {
-    Command::new("sh")
-        .arg("-c")
-        .arg(task_command)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()
-        .expect("Task failed to start!")
-        .wait_with_output()
-        .expect("Failed to wait on the Task!")
+/// Seconds to wait before retrying a Task after a failed attempt
+pub fn retry_delay(task: &Task, retry: u32) -> u64 {
+    let retry_delay = u64::from(task.retry_delay);
+    match task.retry_backoff {
+        RetryBackoff::Fixed => retry_delay,
+        RetryBackoff::Exponential => {
+            // Double the delay for every failed attempt, then add up to 50%
+            // of random jitter so that retries of many Tasks are spread out
+            let exponent = retry.saturating_sub(1).min(16);
+            let delay = retry_delay
+                .saturating_mul(1 << exponent)
+                .min(MAX_RETRY_DELAY);
+            let jitter = rand::thread_rng().gen_range(0..=delay / 2);
+            delay + jitter
+        }
+    }
 }
 
-async fn pipeline_runner(pipeline: Pipeline, scheduled_time: DateTime<Utc>, db_pool: Pool<Sqlite>) {
-    let span = span!(Level::INFO, "PipelineRunner");
-    let _enter = span.enter();
-    info!("Running Pipeline: {}", &pipeline.id);
-    let pipeline_instance = format!("{}_{}", pipeline.id, scheduled_time);
-    let pipeline_id = pipeline.id.clone();
+/// Build a new TaskInstance of a Task within a PipelineRun
+fn new_task_instance(
+    task: &Task,
+    pipeline_run: &PipelineRun,
+    scheduled_time: DateTime<Utc>,
+    attempt: i64,
+    status: TaskStatus,
+    priority_weight: i64,
+) -> TaskInstance {
+    TaskInstance {
+        id: Uuid::new_v4().to_string(),
+        task_id: task.id.clone(),
+        pipeline_id: task.pipeline_id.clone(),
+        pipeline_run_id: Some(pipeline_run.id.clone()),
+        scheduled_time: schedule::format_timestamp(&scheduled_time),
+        status,
+        created_at: schedule::format_timestamp(&Utc::now()),
+        attempt,
+        last_attempt: attempt + i64::from(task.retries),
+        priority_weight,
+        ..Default::default()
+    }
+}
 
-    let tasks = queries::select_task_by_pipeline_id(&pipeline_id, &db_pool)
+/// Move a Task that is waiting on its upstream Tasks to the given status.
+///
+/// A cleared Task already has a pending TaskInstance, otherwise the first
+/// attempt of the Task is recorded with the Task's priority weight.
+async fn resolve_pending_task(
+    task: &Task,
+    pending: Option<&TaskInstance>,
+    pipeline_run: &PipelineRun,
+    scheduled_time: DateTime<Utc>,
+    status: TaskStatus,
+    priority_weights: &HashMap<String, i64>,
+    db_pool: &Pool<Sqlite>,
+) {
+    let result = match pending {
+        Some(task_instance) => {
+            queries::update_pending_task_instance(&task_instance.id, status, db_pool)
+                .await
+                .map(|_| ())
+        }
+        None => {
+            let priority_weight = priority_weights.get(&task.id).copied().unwrap_or(1);
+            let task_instance = new_task_instance(
+                task,
+                pipeline_run,
+                scheduled_time,
+                1,
+                status,
+                priority_weight,
+            );
+            queries::insert_task_instance(task_instance, db_pool)
+                .await
+                .map(|_| ())
+        }
+    };
+    if let Err(e) = result {
+        error!("Failed to record Task '{}' as {}! {}", task.id, status, e);
+    }
+}
+
+/// Queue the next attempt of a Task after a failed attempt, once its retry delay has passed
+async fn queue_retry(task: &Task, failed: &TaskInstance, db_pool: &Pool<Sqlite>) {
+    // Which retry of the Task's current series of attempts this is, from 1
+    let retry = i64::from(task.retries) - (failed.last_attempt - failed.attempt) + 1;
+    let delay = retry_delay(task, u32::try_from(retry.max(1)).unwrap_or(u32::MAX));
+    let run_after = Utc::now() + ChronoDuration::seconds(i64::try_from(delay).unwrap_or(i64::MAX));
+    let attempt = failed.attempt + 1;
+    let task_instance = TaskInstance {
+        id: Uuid::new_v4().to_string(),
+        status: TaskStatus::Queued,
+        execution_start: String::new(),
+        execution_end: String::new(),
+        exit_code: None,
+        created_at: schedule::format_timestamp(&Utc::now()),
+        attempt,
+        run_after: Some(schedule::format_timestamp(&run_after)),
+        ..failed.clone()
+    };
+    match queries::insert_task_instance(task_instance, db_pool).await {
+        Ok(true) => warn!(
+            "Task '{}' failed! Retrying in {} second(s).",
+            task.id, delay
+        ),
+        // Another scheduler tick already queued the retry
+        Ok(false) => debug!("A retry of Task '{}' is already queued.", task.id),
+        Err(e) => error!("Failed to queue a retry of Task '{}'! {}", task.id, e),
+    }
+}
+
+/// Get the most recent attempt of every Task that already ran as part of a PipelineRun
+async fn latest_task_instances(
+    pipeline_run_id: &str,
+    db_pool: &Pool<Sqlite>,
+) -> HashMap<String, TaskInstance> {
+    let task_instances =
+        queries::select_task_instances_by_pipeline_run_id(pipeline_run_id, db_pool)
+            .await
+            .unwrap();
+    let mut latest: HashMap<String, TaskInstance> = HashMap::new();
+    for task_instance in task_instances {
+        let is_later = latest
+            .get(&task_instance.task_id)
+            .is_none_or(|other| task_instance.attempt > other.attempt);
+        if is_later {
+            latest.insert(task_instance.task_id.clone(), task_instance);
+        }
+    }
+    latest
+}
+
+/// Move a running PipelineRun along its DAG, based on the latest TaskInstance of
+/// each of its Tasks.
+///
+/// Tasks whose upstream Tasks all succeeded are queued for an executor, failed
+/// Tasks with retries left are queued again, and Tasks that can no longer run
+/// are recorded as such. The run finishes once none of its Tasks are left to run.
+pub async fn advance_pipeline_run(pipeline_run: &PipelineRun, db_pool: &Pool<Sqlite>) {
+    let scheduled_time = match schedule::parse_timestamp(&pipeline_run.scheduled_time) {
+        Ok(scheduled_time) => scheduled_time,
+        Err(e) => {
+            error!("Pipeline Run '{}' can't be run! {}", pipeline_run.id, e);
+            finish_pipeline_run(pipeline_run, false, db_pool).await;
+            return;
+        }
+    };
+    let tasks = queries::select_task_by_pipeline_id(&pipeline_run.pipeline_id, db_pool)
         .await
         .unwrap();
+    if let Err(e) = dag::validate(&tasks) {
+        error!(
+            "Pipeline '{}' can't be run! {}",
+            pipeline_run.pipeline_id, e
+        );
+        finish_pipeline_run(pipeline_run, false, db_pool).await;
+        return;
+    }
+    let priority_weights = dag::priority_weights(&tasks);
 
-    info!("Executing Pipeline Instance: {}", pipeline_instance);
-    // Spawn a new thread to handle the Pipeline's tasks
-    tokio::task::spawn(async move {
-        let span = span!(Level::INFO, "TaskRunner");
-        let _enter = span.enter();
-        for task in tasks {
-            let execution_start = Utc::now().to_string();
+    let latest_task_instances = latest_task_instances(&pipeline_run.id, db_pool).await;
+    let mut succeeded: HashSet<String> = HashSet::new();
+    let mut failed: HashSet<String> = HashSet::new();
+    // Tasks that were cancelled or skipped
+    let mut stopped: HashSet<String> = HashSet::new();
+    // Tasks that are queued or running
+    let mut active = 0;
+    // Tasks waiting on their upstream Tasks, with their pending TaskInstance if cleared
+    let mut pending: Vec<(Task, Option<&TaskInstance>)> = Vec::new();
+    for task in tasks {
+        let Some(task_instance) = latest_task_instances.get(&task.id) else {
+            pending.push((task, None));
+            continue;
+        };
+        match task_instance.status {
+            TaskStatus::Pending => pending.push((task, Some(task_instance))),
+            TaskStatus::Queued | TaskStatus::Running => active += 1,
+            TaskStatus::Success => {
+                succeeded.insert(task.id);
+            }
+            TaskStatus::Skipped | TaskStatus::Cancelled => {
+                stopped.insert(task.id);
+            }
+            TaskStatus::Failed | TaskStatus::TimedOut | TaskStatus::Zombie
+                if task_instance.attempt < task_instance.last_attempt =>
+            {
+                queue_retry(&task, task_instance, db_pool).await;
+                active += 1;
+            }
+            TaskStatus::Failed
+            | TaskStatus::TimedOut
+            | TaskStatus::Zombie
+            | TaskStatus::UpstreamFailed => {
+                failed.insert(task.id);
+            }
+        }
+    }
+
+    // Tasks downstream of a cancelled or skipped Task are skipped too, and
+    // Tasks downstream of a failure will never run. Repeat until nothing
+    // changes, so that whole chains of downstream Tasks are recorded.
+    loop {
+        let (skipped, remaining): (Vec<_>, Vec<_>) = pending
+            .into_iter()
+            .partition(|(task, _)| task.depends_on.iter().any(|id| stopped.contains(id)));
+        let (blocked, remaining): (Vec<_>, Vec<_>) = remaining
+            .into_iter()
+            .partition(|(task, _)| task.depends_on.iter().any(|id| failed.contains(id)));
+        pending = remaining;
+        if skipped.is_empty() && blocked.is_empty() {
+            break;
+        }
+
+        for (task, task_instance) in skipped {
             info!(
-                "Task '{}' for Pipeline '{}' has started!",
-                task.id, pipeline_id
+                "Task '{}' is skipped because an upstream Task was stopped!",
+                task.id
             );
-            // Run the Task subprocess
-            let result = run_task_command(&task.command);
-            let execution_end = Utc::now().to_string();
-
-            let task_instance_id = format!("{}_{}_{}", task.id, pipeline_id, scheduled_time);
-            let task_instance = TaskInstance {
-                id: task_instance_id,
-                task_id: task.id,
-                execution_start,
-                execution_end,
-                pipeline_id: pipeline_id.clone(),
-                scheduled_time: scheduled_time.to_string(),
-                status: result.status.to_string(),
-                // TODO: add Stderr
-                logs: format!("{:?}", result.stdout),
-                created_at: Utc::now().to_string(),
-            };
-            info!("Saving to database...");
-            queries::insert_task_instance(task_instance, &db_pool)
-                .await
-                .unwrap();
+            resolve_pending_task(
+                &task,
+                task_instance,
+                pipeline_run,
+                scheduled_time,
+                TaskStatus::Skipped,
+                &priority_weights,
+                db_pool,
+            )
+            .await;
+            stopped.insert(task.id);
+        }
+        for (task, task_instance) in blocked {
+            error!(
+                "Task '{}' will not run because an upstream Task failed!",
+                task.id
+            );
+            resolve_pending_task(
+                &task,
+                task_instance,
+                pipeline_run,
+                scheduled_time,
+                TaskStatus::UpstreamFailed,
+                &priority_weights,
+                db_pool,
+            )
+            .await;
+            failed.insert(task.id);
+        }
+    }
+
+    // Queue every Task whose upstreams have all succeeded
+    let (ready, remaining): (Vec<_>, Vec<_>) = pending
+        .into_iter()
+        .partition(|(task, _)| task.depends_on.iter().all(|id| succeeded.contains(id)));
+    for (task, task_instance) in ready {
+        info!(
+            "Task '{}' for Pipeline '{}' is queued!",
+            task.id, task.pipeline_id
+        );
+        resolve_pending_task(
+            &task,
+            task_instance,
+            pipeline_run,
+            scheduled_time,
+            TaskStatus::Queued,
+            &priority_weights,
+            db_pool,
+        )
+        .await;
+        active += 1;
+    }
+
+    if active == 0 && remaining.is_empty() {
+        let success = failed.is_empty() && stopped.is_empty();
+        if success {
+            info!("Pipeline Run '{}' succeeded!", pipeline_run.id);
+        } else {
+            error!("Pipeline Run '{}' failed!", pipeline_run.id);
+        }
+        finish_pipeline_run(pipeline_run, success, db_pool).await;
+    }
+}
+
+/// Record the final status of a running PipelineRun
+async fn finish_pipeline_run(pipeline_run: &PipelineRun, success: bool, db_pool: &Pool<Sqlite>) {
+    let status = if success {
+        PipelineRunStatus::Success
+    } else {
+        PipelineRunStatus::Failed
+    };
+    let execution_end = schedule::format_timestamp(&Utc::now());
+    queries::finish_pipeline_run(&pipeline_run.id, status, &execution_end, db_pool)
+        .await
+        .unwrap();
+}
+
+/// Queue a PipelineRun for each schedule tick that a Pipeline is due for.
+///
+/// Pipelines with `catchup` enabled get a run for every tick missed since
+/// `next_run_at`, otherwise only the most recent tick is run.
+pub async fn schedule_pipeline(pipeline: &Pipeline, now: DateTime<Utc>, db_pool: &Pool<Sqlite>) {
+    let calendar = match queries::select_pipeline_calendar(pipeline, db_pool).await {
+        Ok(calendar) => calendar,
+        Err(e) => {
+            error!("Failed to load the calendar of '{}'! {}", pipeline.id, e);
+            return;
+        }
+    };
+    let schedule = match Schedule::for_pipeline(pipeline, calendar.as_ref()) {
+        Ok(schedule) => schedule,
+        Err(e) => {
+            error!("Pipeline '{}' has an invalid schedule! {}", pipeline.id, e);
+            return;
+        }
+    };
+    let next_scheduled_time = match schedule.next_fire_time(&now) {
+        Ok(next_scheduled_time) => next_scheduled_time,
+        Err(e) => {
+            error!("Pipeline '{}' has an invalid schedule! {}", pipeline.id, e);
+            return;
+        }
+    };
+
+    // Handle new Pipelines
+    let Some(next_run_at) = pipeline.next_run_at.as_deref() else {
+        // Nothing is due once the schedule has passed its end_date
+        let Some(next_scheduled_time) = next_scheduled_time else {
+            return;
+        };
+        info!(
+            "Scheduled new Pipeline '{}'! Next execution at: {}",
+            pipeline.id, next_scheduled_time
+        );
+        let result = queries::init_pipeline_next_run_at(
+            &pipeline.id,
+            &schedule::format_timestamp(&next_scheduled_time),
+            db_pool,
+        )
+        .await;
+        if let Err(e) = result {
+            error!("Failed to schedule Pipeline '{}'! {}", pipeline.id, e);
+        }
+        return;
+    };
 
-            // Store the results in the database
-            if result.status.success() {
-                info!("Task succeeded!");
-            } else {
-                error!("Task failed! Stopping Pipeline.");
-                break;
+    let current_scheduled_time = match schedule::parse_timestamp(next_run_at) {
+        Ok(current_scheduled_time) => current_scheduled_time,
+        Err(e) => {
+            error!(
+                "Pipeline '{}' has an invalid next_run_at! {}",
+                pipeline.id, e
+            );
+            return;
+        }
+    };
+    if current_scheduled_time > now {
+        return;
+    }
+
+    // Every tick from the one we were waiting on up until now was missed
+    let missed_times = schedule.fire_times_between(&current_scheduled_time, &now);
+    let mut missed_times = match missed_times {
+        Ok(missed_times) => missed_times,
+        Err(e) => {
+            error!("Failed to find missed runs for '{}'! {}", pipeline.id, e);
+            vec![current_scheduled_time]
+        }
+    };
+    let latest_time = missed_times.pop().unwrap_or(current_scheduled_time);
+    if !pipeline.catchup {
+        missed_times.clear();
+    }
+
+    let pipeline_runs: Vec<PipelineRun> = missed_times
+        .into_iter()
+        .map(|missed_time| (missed_time, RunTrigger::Backfill))
+        .chain(std::iter::once((latest_time, RunTrigger::Scheduled)))
+        .map(|(scheduled_time, trigger)| PipelineRun {
+            id: Uuid::new_v4().to_string(),
+            pipeline_id: pipeline.id.clone(),
+            scheduled_time: schedule::format_timestamp(&scheduled_time),
+            trigger,
+            status: PipelineRunStatus::Queued,
+            created_at: schedule::format_timestamp(&Utc::now()),
+            ..Default::default()
+        })
+        .collect();
+
+    // Claim the ticks and queue their runs together, so each tick is queued
+    // exactly once and a failure leaves the ticks due for the next loop
+    let claimed = queries::advance_pipeline_schedule(
+        &pipeline.id,
+        next_run_at,
+        &schedule::format_timestamp(&latest_time),
+        next_scheduled_time
+            .map(|time| schedule::format_timestamp(&time))
+            .as_deref(),
+        &pipeline_runs,
+        db_pool,
+    )
+    .await;
+    match claimed {
+        Ok(true) => {}
+        // Another scheduler already claimed the ticks
+        Ok(false) => return,
+        Err(e) => {
+            error!("Failed to queue runs of Pipeline '{}'! {}", pipeline.id, e);
+            return;
+        }
+    }
+    if next_scheduled_time.is_none() {
+        info!("Pipeline '{}' has reached its end_date!", pipeline.id);
+    }
+    info!("Pipeline '{}' is ready for execution!", pipeline.id);
+}
+
+/// Start queued PipelineRuns, limiting how many backfill runs are active at once
+/// and how many runs of each Pipeline are active at once.
+///
+/// A scheduled run that would go over its Pipeline's `max_active_runs` is
+/// handled according to the Pipeline's overlap policy, while manual,
+/// backfill and cleared runs always wait for their turn.
+pub async fn dispatch_pipeline_runs(
+    pipelines: &[Pipeline],
+    settings: &SchedulerSettings,
+    db_pool: &Pool<Sqlite>,
+) {
+    let running_runs = queries::select_pipeline_runs_by_status(PipelineRunStatus::Running, db_pool)
+        .await
+        .unwrap();
+    let mut active_backfill_runs = running_runs
+        .iter()
+        .filter(|pipeline_run| pipeline_run.trigger == RunTrigger::Backfill)
+        .count() as u32;
+    // IDs of the active runs of every Pipeline, oldest tick first
+    let mut active_runs: HashMap<String, Vec<String>> = HashMap::new();
+    for pipeline_run in running_runs {
+        active_runs
+            .entry(pipeline_run.pipeline_id)
+            .or_default()
+            .push(pipeline_run.id);
+    }
+
+    let queued_runs = queries::select_pipeline_runs_by_status(PipelineRunStatus::Queued, db_pool)
+        .await
+        .unwrap();
+    for pipeline_run in queued_runs {
+        let is_backfill = pipeline_run.trigger == RunTrigger::Backfill;
+        if is_backfill && active_backfill_runs >= settings.max_active_backfill_runs {
+            continue;
+        }
+        // Runs of paused Pipelines stay queued until they're resumed
+        let Some(pipeline) = pipelines
+            .iter()
+            .find(|pipeline| pipeline.id == pipeline_run.pipeline_id && !pipeline.is_paused)
+        else {
+            continue;
+        };
+
+        let pipeline_active_runs = active_runs.entry(pipeline.id.clone()).or_default();
+        let max_active_runs = pipeline
+            .max_active_runs
+            .map(|max_active_runs| usize::try_from(max_active_runs.max(1)).unwrap_or(usize::MAX));
+        let is_full = |active: &Vec<String>| max_active_runs.is_some_and(|max| active.len() >= max);
+        // Runs queued again after being cleared already ran once, so they
+        // wait for a free slot rather than being skipped or cancelling others
+        let is_new_scheduled_run =
+            pipeline_run.trigger == RunTrigger::Scheduled && pipeline_run.execution_start.is_none();
+        let overlap_policy = if is_new_scheduled_run {
+            pipeline.overlap_policy
+        } else {
+            OverlapPolicy::Queue
+        };
+        if is_full(pipeline_active_runs) {
+            match overlap_policy {
+                OverlapPolicy::Queue => continue,
+                OverlapPolicy::Skip => {
+                    let execution_end = schedule::format_timestamp(&Utc::now());
+                    queries::skip_pipeline_run(&pipeline_run.id, &execution_end, db_pool)
+                        .await
+                        .unwrap();
+                    warn!(
+                        "Skipped Pipeline Run '{}' of '{}', it already has {} active run(s)!",
+                        pipeline_run.id,
+                        pipeline.id,
+                        pipeline_active_runs.len()
+                    );
+                    continue;
+                }
+                OverlapPolicy::CancelPrevious => {
+                    while is_full(pipeline_active_runs) {
+                        let previous_run_id = pipeline_active_runs.remove(0);
+                        let execution_end = schedule::format_timestamp(&Utc::now());
+                        queries::cancel_pipeline_run(&previous_run_id, &execution_end, db_pool)
+                            .await
+                            .unwrap();
+                        warn!(
+                            "Cancelled Pipeline Run '{}' of '{}' to make room for '{}'!",
+                            previous_run_id, pipeline.id, pipeline_run.id
+                        );
+                    }
+                }
+            }
+        }
+
+        let claimed = queries::start_pipeline_run(
+            &pipeline_run.id,
+            &schedule::format_timestamp(&Utc::now()),
+            db_pool,
+        )
+        .await
+        .unwrap();
+        if claimed {
+            if is_backfill {
+                active_backfill_runs += 1;
+            }
+            pipeline_active_runs.push(pipeline_run.id.clone());
+            info!(
+                "Executing Pipeline Run '{}' of '{}' for {}",
+                pipeline_run.id, pipeline_run.pipeline_id, pipeline_run.scheduled_time
+            );
+        }
+    }
+}
+
+/// Mark running TaskInstances as zombies when their executor or worker stopped
+/// sending heartbeats. Their Tasks are then retried like any other failure.
+async fn detect_zombies(db_pool: &Pool<Sqlite>) {
+    let now = schedule::format_timestamp(&Utc::now());
+    match queries::mark_zombie_task_instances(&now, db_pool).await {
+        Ok(task_instance_ids) => {
+            for task_instance_id in task_instance_ids {
+                error!(
+                    "Task Instance '{}' stopped sending heartbeats! Marked it as a zombie.",
+                    task_instance_id
+                );
+            }
+        }
+        Err(e) => error!("Failed to detect zombie Task Instances! {}", e),
+    }
+}
+
+/// Fail queued TaskInstances that no executor or worker can ever claim, because
+/// their Task's pool doesn't exist or is too small for it
+async fn fail_unclaimable_task_instances(db_pool: &Pool<Sqlite>) {
+    let execution_end = schedule::format_timestamp(&Utc::now());
+    match queries::fail_unclaimable_task_instances(&execution_end, db_pool).await {
+        Ok(task_instance_ids) => {
+            for task_instance_id in task_instance_ids {
+                error!(
+                    "Task Instance '{}' doesn't fit in its pool! Marked it as failed.",
+                    task_instance_id
+                );
             }
         }
-    });
+        Err(e) => error!("Failed to check the pools of Task Instances! {}", e),
+    }
+}
+
+/// Take or renew the leader lease, returning whether this scheduler holds it.
+///
+/// Failing to reach the database counts as not being the leader, so that two
+/// schedulers never act at the same time.
+async fn acquire_leadership(
+    scheduler_id: &str,
+    lease_duration: u64,
+    db_pool: &Pool<Sqlite>,
+) -> bool {
+    let now = Utc::now();
+    let lease_duration = ChronoDuration::seconds(i64::try_from(lease_duration).unwrap_or(i64::MAX));
+    let acquired = queries::acquire_leader_lease(
+        LeaderLease::SCHEDULER,
+        scheduler_id,
+        &schedule::format_timestamp(&now),
+        &schedule::format_timestamp(&(now + lease_duration)),
+        db_pool,
+    )
+    .await;
+    acquired.unwrap_or_else(|e| {
+        error!("Failed to renew the leader lease! {}", e);
+        false
+    })
 }
 
 pub async fn run_scheduler() {
     let span = span!(Level::INFO, "Scheduler");
     let _enter = span.enter();
+    let settings = config::load_config("synth.toml").expect("Failed to load the config!");
     let db_pool = database::get_db_pool().await;
-
-    // In-memory map of the pipelines and their next execution time
-    // TODO: Move this to a database table?
-    let mut pipeline_schedules: HashMap<String, DateTime<Utc>> = HashMap::new();
+    // Identifies this scheduler as the holder of the leader lease
+    let scheduler_id = Uuid::new_v4().to_string();
+    let mut is_leader = false;
+    info!("Scheduler '{}' has started!", scheduler_id);
 
     // This infinite loop is the scheduler
     loop {
         info!("------------------------------");
-        let pipelines: Vec<Pipeline> = sqlx::query_as!(Pipeline, "SELECT * FROM pipelines")
-            .fetch_all(&db_pool)
-            .await
-            .unwrap();
+        // Only the leader schedules, the others stand by to take over once
+        // its lease expires. The lease is renewed every tick, so a tick must
+        // take less time than the lease lasts.
+        let leading = acquire_leadership(
+            &scheduler_id,
+            settings.scheduler.leader_lease_duration,
+            &db_pool,
+        )
+        .await;
+        if leading && !is_leader {
+            info!("Scheduler '{}' is now the leader!", scheduler_id);
+        } else if !leading && is_leader {
+            warn!("Scheduler '{}' is no longer the leader!", scheduler_id);
+        }
+        is_leader = leading;
+        if !is_leader {
+            info!("Standing by...");
+            async_sleep(config::SCHEDULER_INTERVAL).await;
+            continue;
+        }
 
-        // NOTE: Easily parallelizable
-        for pipeline in pipelines {
-            // The 'parse' method returns the next execution time
-            let next_scheduled_time: DateTime<Utc> =
-                parse(&pipeline.schedule, &Utc::now()).unwrap();
-            let current_scheduled_time =
-                pipeline_schedules.insert(pipeline.id.clone(), next_scheduled_time);
-
-            // Handle new Pipelines
-            if current_scheduled_time.is_none() {
-                info!(
-                    "Added '{}' to the HashMap! Next execution at: {}",
-                    pipeline.id, next_scheduled_time
-                );
-                continue;
-            }
+        // Runs as soon as this scheduler leads too, catching TaskInstances
+        // orphaned while no scheduler was up, before their runs are advanced
+        detect_zombies(&db_pool).await;
+        fail_unclaimable_task_instances(&db_pool).await;
+        let pipelines = queries::select_pipelines(&db_pool).await.unwrap();
 
-            let current_scheduled_time = current_scheduled_time.unwrap();
-            let requires_execution = current_scheduled_time != next_scheduled_time;
+        // NOTE: Easily parallelizable
+        // Paused Pipelines keep their next_run_at, so catchup applies once resumed
+        for pipeline in pipelines.iter().filter(|pipeline| !pipeline.is_paused) {
+            schedule_pipeline(pipeline, Utc::now(), &db_pool).await;
+        }
+        dispatch_pipeline_runs(&pipelines, &settings.scheduler, &db_pool).await;
 
-            if requires_execution {
-                info!("Pipeline '{}' is ready for execution!", pipeline.id);
-                pipeline_schedules.insert(pipeline.id.clone(), next_scheduled_time);
-                pipeline_runner(pipeline.clone(), current_scheduled_time, db_pool.clone()).await;
-            }
+        // Runs that already started keep going, even if their Pipeline was paused
+        let running_runs =
+            queries::select_pipeline_runs_by_status(PipelineRunStatus::Running, &db_pool)
+                .await
+                .unwrap();
+        for pipeline_run in &running_runs {
+            advance_pipeline_run(pipeline_run, &db_pool).await;
         }
 
         // Sleep a tad to avoid resource saturation
-        async_sleep(5).await;
+        async_sleep(config::SCHEDULER_INTERVAL).await;
     }
 }
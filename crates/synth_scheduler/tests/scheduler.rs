@@ -0,0 +1,393 @@
+use chrono::{DateTime, Duration, Utc};
+use pretty_assertions::assert_eq;
+use sqlx::SqlitePool;
+use synth_common::config::{self, BuildUrl, SchedulerSettings};
+use synth_common::models::{
+    OverlapPolicy, Pipeline, PipelineRun, PipelineRunStatus, RetryBackoff, RunTrigger, Task,
+    TaskInstance, TaskStatus,
+};
+use synth_common::{database, queries, schedule};
+use synth_scheduler::scheduler;
+use uuid::Uuid;
+
+/// Create a migrated test database and return a pool for it
+async fn spawn_db() -> SqlitePool {
+    let mut config =
+        config::load_config("../../synth.toml").expect("Failed to load configuration!");
+    config.database.database = format!("test-{}", Uuid::new_v4());
+    let db_url = &config.database.build_url();
+    database::setupdb(db_url)
+        .await
+        .expect("Failed to setup test database!");
+    SqlitePool::connect(db_url)
+        .await
+        .expect("Failed to create the database pool!")
+}
+
+/// Queue a scheduled PipelineRun of a Pipeline for the tick at `hour`
+async fn queue_pipeline_run(pipeline_id: &str, hour: u32, db_pool: &SqlitePool) -> String {
+    let pipeline_run = PipelineRun {
+        id: format!("{}run{}", pipeline_id, hour),
+        pipeline_id: pipeline_id.to_owned(),
+        scheduled_time: format!("2024-01-08T{:02}:00:00Z", hour),
+        ..Default::default()
+    };
+    queries::insert_pipeline_run(&pipeline_run, db_pool)
+        .await
+        .expect("Failed to insert pipeline run!");
+    pipeline_run.id
+}
+
+/// The time `time` on the day the test runs are scheduled for
+fn at(time: &str) -> DateTime<Utc> {
+    schedule::parse_timestamp(&format!("2024-01-08T{}Z", time)).unwrap()
+}
+
+/// Schedule a Pipeline the way the scheduler loop does, reading it back from
+/// the database first as a restarted scheduler would
+async fn schedule_at(pipeline_id: &str, now: DateTime<Utc>, db_pool: &SqlitePool) {
+    let pipeline = queries::select_pipeline_by_id(pipeline_id, db_pool)
+        .await
+        .unwrap();
+    scheduler::schedule_pipeline(&pipeline, now, db_pool).await;
+}
+
+/// The ticks and triggers of a Pipeline's runs, oldest first
+async fn scheduled_runs(pipeline_id: &str, db_pool: &SqlitePool) -> Vec<(String, RunTrigger)> {
+    let mut pipeline_runs = queries::select_pipeline_runs_by_pipeline_id(pipeline_id, db_pool)
+        .await
+        .unwrap();
+    pipeline_runs.reverse();
+    pipeline_runs
+        .into_iter()
+        .map(|pipeline_run| (pipeline_run.scheduled_time, pipeline_run.trigger))
+        .collect()
+}
+
+/// Insert an hourly Pipeline that was last scheduled at 05:30, then scheduled
+/// again at 09:30 after missing the ticks in between
+async fn schedule_after_downtime(pipeline_id: &str, catchup: bool, db_pool: &SqlitePool) {
+    let pipeline = Pipeline {
+        id: pipeline_id.to_owned(),
+        schedule: "0 * * * *".to_owned(),
+        catchup,
+        ..Default::default()
+    };
+    queries::insert_pipeline(pipeline, db_pool).await.unwrap();
+    schedule_at(pipeline_id, at("05:30:00"), db_pool).await;
+    schedule_at(pipeline_id, at("09:30:00"), db_pool).await;
+}
+
+async fn pipeline_run_status(pipeline_run_id: &str, db_pool: &SqlitePool) -> PipelineRunStatus {
+    queries::select_pipeline_run_by_id(pipeline_run_id, db_pool)
+        .await
+        .unwrap()
+        .status
+}
+
+#[tokio::test]
+async fn cleared_run_waits_instead_of_following_the_overlap_policy() {
+    // Arrange
+    let db_pool = spawn_db().await;
+    let pipeline = Pipeline {
+        id: "pipeline1".to_owned(),
+        max_active_runs: Some(1),
+        overlap_policy: OverlapPolicy::Skip,
+        ..Default::default()
+    };
+    let cleared_run_id = queue_pipeline_run("pipeline1", 8, &db_pool).await;
+    queries::start_pipeline_run(&cleared_run_id, "2024-01-08T08:00:00Z", &db_pool)
+        .await
+        .unwrap();
+    queries::finish_pipeline_run(
+        &cleared_run_id,
+        PipelineRunStatus::Failed,
+        "2024-01-08T08:05:00Z",
+        &db_pool,
+    )
+    .await
+    .unwrap();
+    let running_run_id = queue_pipeline_run("pipeline1", 9, &db_pool).await;
+    queries::start_pipeline_run(&running_run_id, "2024-01-08T09:00:00Z", &db_pool)
+        .await
+        .unwrap();
+    queries::clear_task_instances(&cleared_run_id, &[], &db_pool)
+        .await
+        .unwrap();
+    let new_run_id = queue_pipeline_run("pipeline1", 10, &db_pool).await;
+
+    // Act
+    scheduler::dispatch_pipeline_runs(&[pipeline], &SchedulerSettings::default(), &db_pool).await;
+
+    // Assert that only the run that never started was skipped
+    assert_eq!(
+        pipeline_run_status(&cleared_run_id, &db_pool).await,
+        PipelineRunStatus::Queued
+    );
+    assert_eq!(
+        pipeline_run_status(&running_run_id, &db_pool).await,
+        PipelineRunStatus::Running
+    );
+    assert_eq!(
+        pipeline_run_status(&new_run_id, &db_pool).await,
+        PipelineRunStatus::Skipped
+    );
+}
+
+#[test]
+fn fixed_retry_delay_stays_the_same() {
+    let task = Task {
+        retry_delay: 30,
+        retry_backoff: RetryBackoff::Fixed,
+        ..Default::default()
+    };
+    for retry in [1, 2, 10, u32::MAX] {
+        assert_eq!(scheduler::retry_delay(&task, retry), 30);
+    }
+}
+
+#[test]
+fn exponential_retry_delay_doubles_with_jitter() {
+    let task = Task {
+        retry_delay: 30,
+        retry_backoff: RetryBackoff::Exponential,
+        ..Default::default()
+    };
+    // The first retry waits the plain delay, plus up to 50% of jitter
+    for (retry, delay) in [(1, 30), (2, 60), (3, 120)] {
+        let retry_delay = scheduler::retry_delay(&task, retry);
+        assert!(
+            (delay..=delay + delay / 2).contains(&retry_delay),
+            "Retry {} waited {} second(s)",
+            retry,
+            retry_delay
+        );
+    }
+}
+
+#[test]
+fn exponential_retry_delay_is_capped_for_many_retries() {
+    let task = Task {
+        retry_delay: u32::MAX,
+        retry_backoff: RetryBackoff::Exponential,
+        ..Default::default()
+    };
+    // Capped at an hour, plus up to 50% of jitter
+    for retry in [20, 64, u32::MAX] {
+        let retry_delay = scheduler::retry_delay(&task, retry);
+        assert!((3600..=5400).contains(&retry_delay));
+    }
+}
+
+#[tokio::test]
+async fn failed_attempt_is_retried_until_the_last_attempt_fails_the_run() {
+    // Arrange
+    let db_pool = spawn_db().await;
+    let pipeline = Pipeline {
+        id: "retrypipeline".to_owned(),
+        schedule: "0 * * * *".to_owned(),
+        ..Default::default()
+    };
+    queries::insert_pipeline(pipeline, &db_pool).await.unwrap();
+    let task = Task {
+        id: "retriedtask".to_owned(),
+        pipeline_id: "retrypipeline".to_owned(),
+        command: "exit 1".to_owned(),
+        retries: 1,
+        retry_delay: 60,
+        retry_backoff: RetryBackoff::Fixed,
+        ..Default::default()
+    };
+    queries::upsert_task(&task, &db_pool).await.unwrap();
+    let pipeline_run_id = queue_pipeline_run("retrypipeline", 9, &db_pool).await;
+    queries::start_pipeline_run(&pipeline_run_id, "2024-01-08T09:00:00Z", &db_pool)
+        .await
+        .unwrap();
+    queries::insert_task_instance(
+        TaskInstance {
+            id: "firstattempt".to_owned(),
+            task_id: "retriedtask".to_owned(),
+            pipeline_id: "retrypipeline".to_owned(),
+            pipeline_run_id: Some(pipeline_run_id.clone()),
+            status: TaskStatus::Failed,
+            attempt: 1,
+            last_attempt: 2,
+            ..Default::default()
+        },
+        &db_pool,
+    )
+    .await
+    .unwrap();
+    let pipeline_run = queries::select_pipeline_run_by_id(&pipeline_run_id, &db_pool)
+        .await
+        .unwrap();
+
+    // Act
+    let before_retry = Utc::now();
+    scheduler::advance_pipeline_run(&pipeline_run, &db_pool).await;
+
+    // Assert that the second attempt waits for the retry delay
+    let task_instances =
+        queries::select_task_instances_by_pipeline_run_id(&pipeline_run_id, &db_pool)
+            .await
+            .unwrap();
+    let retry = task_instances
+        .iter()
+        .find(|task_instance| task_instance.attempt == 2)
+        .expect("The failed attempt wasn't retried!");
+    assert_eq!(retry.status, TaskStatus::Queued);
+    let run_after = schedule::parse_timestamp(retry.run_after.as_deref().unwrap()).unwrap();
+    let earliest = before_retry + Duration::seconds(59);
+    let latest = Utc::now() + Duration::seconds(60);
+    assert!(earliest <= run_after && run_after <= latest);
+    assert_eq!(
+        pipeline_run_status(&pipeline_run_id, &db_pool).await,
+        PipelineRunStatus::Running
+    );
+
+    // Act
+    let claimed = queries::claim_task_instance(
+        "executor",
+        "9999-01-01T00:00:00Z",
+        "9999-01-01T00:00:30Z",
+        &db_pool,
+    )
+    .await
+    .unwrap()
+    .unwrap();
+    queries::finish_task_instance(
+        &claimed.id,
+        "executor",
+        TaskStatus::Failed,
+        Some(1),
+        "9999-01-01T00:00:01Z",
+        &db_pool,
+    )
+    .await
+    .unwrap();
+    scheduler::advance_pipeline_run(&pipeline_run, &db_pool).await;
+
+    // Assert that the last attempt failing fails the run
+    assert_eq!(claimed.id, retry.id);
+    assert_eq!(
+        queries::select_task_instances_by_pipeline_run_id(&pipeline_run_id, &db_pool)
+            .await
+            .unwrap()
+            .len(),
+        2
+    );
+    assert_eq!(
+        pipeline_run_status(&pipeline_run_id, &db_pool).await,
+        PipelineRunStatus::Failed
+    );
+}
+
+#[test]
+fn config_with_too_short_leader_lease_fails() {
+    let config = std::fs::read_to_string("../../synth.toml").expect("Failed to read the config!");
+    let setting = "leader_lease_duration = 30";
+    assert!(config.contains(setting));
+    for leader_lease_duration in [0, config::SCHEDULER_INTERVAL] {
+        let filepath = std::env::temp_dir().join(format!("synth-{}.toml", Uuid::new_v4()));
+        std::fs::write(
+            &filepath,
+            config.replace(
+                setting,
+                &format!("leader_lease_duration = {}", leader_lease_duration),
+            ),
+        )
+        .unwrap();
+
+        let result = config::load_config(filepath.to_str().unwrap());
+        std::fs::remove_file(&filepath).unwrap();
+
+        assert!(
+            result.is_err(),
+            "Accepted a leader lease of {} second(s)",
+            leader_lease_duration
+        );
+    }
+}
+
+#[tokio::test]
+async fn restarted_scheduler_resumes_from_the_stored_next_run_at() {
+    // Arrange
+    let db_pool = spawn_db().await;
+    let pipeline = Pipeline {
+        id: "restartpipeline".to_owned(),
+        schedule: "0 * * * *".to_owned(),
+        ..Default::default()
+    };
+    queries::insert_pipeline(pipeline, &db_pool).await.unwrap();
+    schedule_at("restartpipeline", at("08:30:00"), &db_pool).await;
+
+    // Act, as a new scheduler that only knows what was stored
+    schedule_at("restartpipeline", at("09:10:00"), &db_pool).await;
+    schedule_at("restartpipeline", at("09:20:00"), &db_pool).await;
+
+    // Assert that the stored tick ran once, rather than the next one from now
+    assert_eq!(
+        scheduled_runs("restartpipeline", &db_pool).await,
+        vec![("2024-01-08T09:00:00Z".to_owned(), RunTrigger::Scheduled)]
+    );
+    let pipeline = queries::select_pipeline_by_id("restartpipeline", &db_pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        pipeline.next_run_at.as_deref(),
+        Some("2024-01-08T10:00:00Z")
+    );
+    assert_eq!(
+        pipeline.last_run_at.as_deref(),
+        Some("2024-01-08T09:00:00Z")
+    );
+}
+
+#[tokio::test]
+async fn missed_ticks_are_skipped_without_catchup() {
+    // Arrange
+    let db_pool = spawn_db().await;
+
+    // Act
+    schedule_after_downtime("skippingpipeline", false, &db_pool).await;
+
+    // Assert that only the latest missed tick gets a run
+    assert_eq!(
+        scheduled_runs("skippingpipeline", &db_pool).await,
+        vec![("2024-01-08T09:00:00Z".to_owned(), RunTrigger::Scheduled)]
+    );
+    let pipeline = queries::select_pipeline_by_id("skippingpipeline", &db_pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        pipeline.next_run_at.as_deref(),
+        Some("2024-01-08T10:00:00Z")
+    );
+}
+
+#[tokio::test]
+async fn missed_ticks_each_get_a_run_with_catchup() {
+    // Arrange
+    let db_pool = spawn_db().await;
+
+    // Act
+    schedule_after_downtime("catchuppipeline", true, &db_pool).await;
+
+    // Assert that every missed tick gets a run, the latest as the scheduled one
+    assert_eq!(
+        scheduled_runs("catchuppipeline", &db_pool).await,
+        vec![
+            ("2024-01-08T06:00:00Z".to_owned(), RunTrigger::Backfill),
+            ("2024-01-08T07:00:00Z".to_owned(), RunTrigger::Backfill),
+            ("2024-01-08T08:00:00Z".to_owned(), RunTrigger::Backfill),
+            ("2024-01-08T09:00:00Z".to_owned(), RunTrigger::Scheduled),
+        ]
+    );
+    let pipeline = queries::select_pipeline_by_id("catchuppipeline", &db_pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        pipeline.next_run_at.as_deref(),
+        Some("2024-01-08T10:00:00Z")
+    );
+}